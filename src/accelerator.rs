@@ -0,0 +1,457 @@
+//! Key names and accelerator strings such as `Ctrl+Shift+K`.
+
+use std::fmt;
+use std::str::FromStr;
+use crate::hotkey::Hotkey;
+use crate::input_handler::KeyCode;
+use crate::modifiers::Modifiers;
+
+/// An error from parsing a key name or an accelerator string.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseKeyError {
+    message: String,
+    token: String,
+    position: usize,
+}
+
+impl ParseKeyError {
+    pub(crate) fn new(message: &str, token: &str, position: usize) -> Self {
+        ParseKeyError {
+            message: message.to_string(),
+            token: token.to_string(),
+            position,
+        }
+    }
+
+    /// Moves the position of the error, for input that was parsed in parts.
+    pub(crate) fn offset_by(mut self, offset: usize) -> Self {
+        self.position += offset;
+        self
+    }
+
+    /// Returns the part of the input that couldn't be parsed.
+    pub fn token(&self) -> &str {
+        &self.token
+    }
+
+    /// Returns the byte offset of the bad token in the input.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+}
+
+impl fmt::Display for ParseKeyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.token.is_empty() {
+            write!(f, "{} at position {}", self.message, self.position)
+        } else {
+            write!(f, "{} `{}` at position {}", self.message, self.token, self.position)
+        }
+    }
+}
+
+impl std::error::Error for ParseKeyError {}
+
+/// How to display an accelerator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AcceleratorStyle {
+    /// Modifier and key names joined with `+`, such as `Ctrl+Shift+K`
+    Text,
+    /// macOS menu symbols, such as `⌘⇧K`
+    Symbols,
+}
+
+impl AcceleratorStyle {
+    /// Returns the style conventional on the current platform.
+    pub fn native() -> Self {
+        if cfg!(target_os = "macos") { AcceleratorStyle::Symbols } else { AcceleratorStyle::Text }
+    }
+}
+
+impl KeyCode {
+    /// Returns the canonical name of the key, as accepted by `parse`.
+    pub fn name(self) -> &'static str {
+        match self {
+            KeyCode::KeyEsc => "Esc",
+            KeyCode::Key1 => "1",
+            KeyCode::Key2 => "2",
+            KeyCode::Key3 => "3",
+            KeyCode::Key4 => "4",
+            KeyCode::Key5 => "5",
+            KeyCode::Key6 => "6",
+            KeyCode::Key7 => "7",
+            KeyCode::Key8 => "8",
+            KeyCode::Key9 => "9",
+            KeyCode::Key0 => "0",
+            KeyCode::KeyMinus => "-",
+            KeyCode::KeyEqual => "=",
+            KeyCode::KeyBackspace => "Backspace",
+            KeyCode::KeyTab => "Tab",
+            KeyCode::KeyQ => "Q",
+            KeyCode::KeyW => "W",
+            KeyCode::KeyE => "E",
+            KeyCode::KeyR => "R",
+            KeyCode::KeyT => "T",
+            KeyCode::KeyY => "Y",
+            KeyCode::KeyU => "U",
+            KeyCode::KeyI => "I",
+            KeyCode::KeyO => "O",
+            KeyCode::KeyP => "P",
+            KeyCode::KeyLeftBrace => "[",
+            KeyCode::KeyRightBrace => "]",
+            KeyCode::KeyEnter => "Enter",
+            KeyCode::KeyLeftCtrl => "LeftCtrl",
+            KeyCode::KeyA => "A",
+            KeyCode::KeyS => "S",
+            KeyCode::KeyD => "D",
+            KeyCode::KeyF => "F",
+            KeyCode::KeyG => "G",
+            KeyCode::KeyH => "H",
+            KeyCode::KeyJ => "J",
+            KeyCode::KeyK => "K",
+            KeyCode::KeyL => "L",
+            KeyCode::KeySemicolon => ";",
+            KeyCode::KeyApostrophe => "'",
+            KeyCode::KeyGrave => "`",
+            KeyCode::KeyLeftShift => "LeftShift",
+            KeyCode::KeyBackslash => "\\",
+            KeyCode::KeyZ => "Z",
+            KeyCode::KeyX => "X",
+            KeyCode::KeyC => "C",
+            KeyCode::KeyV => "V",
+            KeyCode::KeyB => "B",
+            KeyCode::KeyN => "N",
+            KeyCode::KeyM => "M",
+            KeyCode::KeyComma => ",",
+            KeyCode::KeyDot => ".",
+            KeyCode::KeySlash => "/",
+            KeyCode::KeyRightShift => "RightShift",
+            KeyCode::KeyKpAsterisk => "NumpadMultiply",
+            KeyCode::KeyLeftAlt => "LeftAlt",
+            KeyCode::KeySpace => "Space",
+            KeyCode::KeyCapslock => "CapsLock",
+            KeyCode::KeyF1 => "F1",
+            KeyCode::KeyF2 => "F2",
+            KeyCode::KeyF3 => "F3",
+            KeyCode::KeyF4 => "F4",
+            KeyCode::KeyF5 => "F5",
+            KeyCode::KeyF6 => "F6",
+            KeyCode::KeyF7 => "F7",
+            KeyCode::KeyF8 => "F8",
+            KeyCode::KeyF9 => "F9",
+            KeyCode::KeyF10 => "F10",
+            KeyCode::KeyF11 => "F11",
+            KeyCode::KeyF12 => "F12",
+            KeyCode::KeyUp => "Up",
+            KeyCode::KeyDown => "Down",
+            KeyCode::KeyLeft => "Left",
+            KeyCode::KeyRight => "Right",
+            KeyCode::KeyRightCtrl => "RightCtrl",
+            KeyCode::KeyRightAlt => "RightAlt",
+            KeyCode::KeyLeftMeta => "LeftSuper",
+            KeyCode::KeyRightMeta => "RightSuper",
+        }
+    }
+
+    /// Returns the macOS menu symbol for the key, or its name if it has none.
+    fn symbol(self) -> &'static str {
+        match self {
+            KeyCode::KeyEsc => "⎋",
+            KeyCode::KeyBackspace => "⌫",
+            KeyCode::KeyTab => "⇥",
+            KeyCode::KeyEnter => "↩",
+            KeyCode::KeyCapslock => "⇪",
+            KeyCode::KeySpace => "␣",
+            KeyCode::KeyUp => "↑",
+            KeyCode::KeyDown => "↓",
+            KeyCode::KeyLeft => "←",
+            KeyCode::KeyRight => "→",
+            key => key.name(),
+        }
+    }
+}
+
+impl fmt::Display for KeyCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for KeyCode {
+    type Err = ParseKeyError;
+
+    /// Parses a key name, ignoring case. Besides the names returned by [`KeyCode::name`],
+    /// common aliases such as `Escape`, `Return` and `Minus` are accepted, as are the symbols
+    /// of [`AcceleratorStyle::Symbols`], such as `↩`.
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Ok(match name.to_ascii_lowercase().as_str() {
+            "esc" | "escape" | "⎋" => KeyCode::KeyEsc,
+            "1" => KeyCode::Key1,
+            "2" => KeyCode::Key2,
+            "3" => KeyCode::Key3,
+            "4" => KeyCode::Key4,
+            "5" => KeyCode::Key5,
+            "6" => KeyCode::Key6,
+            "7" => KeyCode::Key7,
+            "8" => KeyCode::Key8,
+            "9" => KeyCode::Key9,
+            "0" => KeyCode::Key0,
+            "-" | "minus" => KeyCode::KeyMinus,
+            "=" | "equal" | "equals" => KeyCode::KeyEqual,
+            "backspace" | "⌫" => KeyCode::KeyBackspace,
+            "tab" | "⇥" => KeyCode::KeyTab,
+            "q" => KeyCode::KeyQ,
+            "w" => KeyCode::KeyW,
+            "e" => KeyCode::KeyE,
+            "r" => KeyCode::KeyR,
+            "t" => KeyCode::KeyT,
+            "y" => KeyCode::KeyY,
+            "u" => KeyCode::KeyU,
+            "i" => KeyCode::KeyI,
+            "o" => KeyCode::KeyO,
+            "p" => KeyCode::KeyP,
+            "[" | "leftbracket" | "bracketleft" => KeyCode::KeyLeftBrace,
+            "]" | "rightbracket" | "bracketright" => KeyCode::KeyRightBrace,
+            "enter" | "return" | "↩" => KeyCode::KeyEnter,
+            "leftctrl" | "leftcontrol" => KeyCode::KeyLeftCtrl,
+            "a" => KeyCode::KeyA,
+            "s" => KeyCode::KeyS,
+            "d" => KeyCode::KeyD,
+            "f" => KeyCode::KeyF,
+            "g" => KeyCode::KeyG,
+            "h" => KeyCode::KeyH,
+            "j" => KeyCode::KeyJ,
+            "k" => KeyCode::KeyK,
+            "l" => KeyCode::KeyL,
+            ";" | "semicolon" => KeyCode::KeySemicolon,
+            "'" | "apostrophe" | "quote" => KeyCode::KeyApostrophe,
+            "`" | "grave" | "backquote" | "backtick" => KeyCode::KeyGrave,
+            "leftshift" => KeyCode::KeyLeftShift,
+            "\\" | "backslash" => KeyCode::KeyBackslash,
+            "z" => KeyCode::KeyZ,
+            "x" => KeyCode::KeyX,
+            "c" => KeyCode::KeyC,
+            "v" => KeyCode::KeyV,
+            "b" => KeyCode::KeyB,
+            "n" => KeyCode::KeyN,
+            "m" => KeyCode::KeyM,
+            "," | "comma" => KeyCode::KeyComma,
+            "." | "dot" | "period" => KeyCode::KeyDot,
+            "/" | "slash" => KeyCode::KeySlash,
+            "rightshift" => KeyCode::KeyRightShift,
+            "numpadmultiply" | "kpasterisk" => KeyCode::KeyKpAsterisk,
+            "leftalt" | "leftoption" => KeyCode::KeyLeftAlt,
+            "space" | "spacebar" | "␣" => KeyCode::KeySpace,
+            "capslock" | "⇪" => KeyCode::KeyCapslock,
+            "f1" => KeyCode::KeyF1,
+            "f2" => KeyCode::KeyF2,
+            "f3" => KeyCode::KeyF3,
+            "f4" => KeyCode::KeyF4,
+            "f5" => KeyCode::KeyF5,
+            "f6" => KeyCode::KeyF6,
+            "f7" => KeyCode::KeyF7,
+            "f8" => KeyCode::KeyF8,
+            "f9" => KeyCode::KeyF9,
+            "f10" => KeyCode::KeyF10,
+            "f11" => KeyCode::KeyF11,
+            "f12" => KeyCode::KeyF12,
+            "up" | "arrowup" | "↑" => KeyCode::KeyUp,
+            "down" | "arrowdown" | "↓" => KeyCode::KeyDown,
+            "left" | "arrowleft" | "←" => KeyCode::KeyLeft,
+            "right" | "arrowright" | "→" => KeyCode::KeyRight,
+            "rightctrl" | "rightcontrol" => KeyCode::KeyRightCtrl,
+            "rightalt" | "rightoption" => KeyCode::KeyRightAlt,
+            "leftsuper" | "leftmeta" | "leftcmd" | "leftwin" => KeyCode::KeyLeftMeta,
+            "rightsuper" | "rightmeta" | "rightcmd" | "rightwin" => KeyCode::KeyRightMeta,
+            _ => return Err(ParseKeyError::new("unknown key", name, 0)),
+        })
+    }
+}
+
+/// Parses a modifier name in an accelerator, ignoring case.
+fn parse_modifier(name: &str) -> Option<Modifiers> {
+    Some(match name.to_ascii_lowercase().as_str() {
+        "ctrl" | "control" => Modifiers::CTRL,
+        "shift" => Modifiers::SHIFT,
+        "alt" | "option" | "opt" => Modifiers::ALT,
+        "altgr" => Modifiers::ALTGR,
+        "super" | "cmd" | "command" | "win" | "windows" | "meta" | "logo" => Modifiers::SUPER,
+        _ => return None,
+    })
+}
+
+/// Parses a modifier symbol in an accelerator.
+fn parse_modifier_symbol(c: char) -> Option<Modifiers> {
+    MODIFIER_NAMES.iter().find(|(_, _, symbol)| *symbol == c).map(|(flag, _, _)| *flag)
+}
+
+/// Modifiers in the order they are displayed, with their text and symbol forms. macOS has no
+/// AltGr, so it uses the ISO symbol for the third level key.
+const MODIFIER_NAMES: [(Modifiers, &str, char); 5] = [
+    (Modifiers::CTRL, "Ctrl", '⌃'),
+    (Modifiers::ALT, "Alt", '⌥'),
+    (Modifiers::ALTGR, "AltGr", '⇮'),
+    (Modifiers::SHIFT, "Shift", '⇧'),
+    (Modifiers::SUPER, "Super", '⌘'),
+];
+
+impl Hotkey {
+    /// Formats the hotkey in the given style, which parses back to the same hotkey. Lock
+    /// states are left out.
+    pub fn format(&self, style: AcceleratorStyle) -> String {
+        let modifiers = MODIFIER_NAMES.iter().filter(|(flag, _, _)| self.modifiers.contains(*flag));
+        match style {
+            AcceleratorStyle::Text => {
+                let mut parts: Vec<_> = modifiers.map(|(_, name, _)| *name).collect();
+                parts.push(self.key.name());
+                parts.join("+")
+            }
+            AcceleratorStyle::Symbols => {
+                let mut text: String = modifiers.map(|(_, _, symbol)| *symbol).collect();
+                text.push_str(self.key.symbol());
+                text
+            }
+        }
+    }
+}
+
+impl fmt::Display for Hotkey {
+    /// Formats the hotkey as text, such as `Ctrl+Shift+K`, which parses back to the same
+    /// hotkey.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.format(AcceleratorStyle::Text))
+    }
+}
+
+impl FromStr for Hotkey {
+    type Err = ParseKeyError;
+
+    /// Parses an accelerator such as `Ctrl+Shift+K`: any number of modifiers followed by a key,
+    /// joined with `+`. Names are case-insensitive, and `Cmd`, `Win` and `Meta` are accepted
+    /// for `Super`, as is `Option` for `Alt`. The symbol style, such as `⌃⇧K`, is accepted too.
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut modifiers = Modifiers::empty();
+
+        // In the symbol style, modifier symbols are followed by the key without separators.
+        let mut rest = text;
+        while let Some(c) = rest.chars().next()
+            && let Some(modifier) = parse_modifier_symbol(c)
+        {
+            let start = text.len() - rest.len();
+            if modifiers.contains(modifier) {
+                return Err(ParseKeyError::new("repeated modifier", &rest[..c.len_utf8()], start));
+            }
+            modifiers |= modifier;
+            rest = &rest[c.len_utf8()..];
+        }
+        if !modifiers.is_empty() {
+            let start = text.len() - rest.trim_start().len();
+            let key = rest.trim();
+            if key.is_empty() {
+                return Err(ParseKeyError::new("missing key name", key, start));
+            }
+            let key = key.parse::<KeyCode>().map_err(|err| err.offset_by(start))?;
+            return Ok(Hotkey { modifiers, key });
+        }
+
+        let mut position = 0;
+        let mut tokens = text.split('+').peekable();
+        while let Some(token) = tokens.next() {
+            let trimmed = token.trim();
+            let start = position + (token.len() - token.trim_start().len());
+            position += token.len() + 1;
+
+            if trimmed.is_empty() {
+                return Err(ParseKeyError::new("missing key name", trimmed, start));
+            }
+            if tokens.peek().is_none() {
+                let key = trimmed.parse::<KeyCode>()
+                    .map_err(|_| {
+                        let message = if parse_modifier(trimmed).is_some() { "expected a key after modifier" } else { "unknown key" };
+                        ParseKeyError::new(message, trimmed, start)
+                    })?;
+                return Ok(Hotkey { modifiers, key });
+            }
+
+            let Some(modifier) = parse_modifier(trimmed) else {
+                return Err(ParseKeyError::new("unknown modifier", trimmed, start));
+            };
+            if modifiers.contains(modifier) {
+                return Err(ParseKeyError::new("repeated modifier", trimmed, start));
+            }
+            modifiers |= modifier;
+        }
+        unreachable!("split always yields at least one token")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEYS: &[KeyCode] = &[
+        KeyCode::KeyEsc, KeyCode::Key1, KeyCode::Key2, KeyCode::Key3, KeyCode::Key4, KeyCode::Key5,
+        KeyCode::Key6, KeyCode::Key7, KeyCode::Key8, KeyCode::Key9, KeyCode::Key0, KeyCode::KeyMinus,
+        KeyCode::KeyEqual, KeyCode::KeyBackspace, KeyCode::KeyTab, KeyCode::KeyQ, KeyCode::KeyW,
+        KeyCode::KeyE, KeyCode::KeyR, KeyCode::KeyT, KeyCode::KeyY, KeyCode::KeyU, KeyCode::KeyI,
+        KeyCode::KeyO, KeyCode::KeyP, KeyCode::KeyLeftBrace, KeyCode::KeyRightBrace, KeyCode::KeyEnter,
+        KeyCode::KeyLeftCtrl, KeyCode::KeyA, KeyCode::KeyS, KeyCode::KeyD, KeyCode::KeyF, KeyCode::KeyG,
+        KeyCode::KeyH, KeyCode::KeyJ, KeyCode::KeyK, KeyCode::KeyL, KeyCode::KeySemicolon,
+        KeyCode::KeyApostrophe, KeyCode::KeyGrave, KeyCode::KeyLeftShift, KeyCode::KeyBackslash,
+        KeyCode::KeyZ, KeyCode::KeyX, KeyCode::KeyC, KeyCode::KeyV, KeyCode::KeyB, KeyCode::KeyN,
+        KeyCode::KeyM, KeyCode::KeyComma, KeyCode::KeyDot, KeyCode::KeySlash, KeyCode::KeyRightShift,
+        KeyCode::KeyKpAsterisk, KeyCode::KeyLeftAlt, KeyCode::KeySpace, KeyCode::KeyCapslock,
+        KeyCode::KeyF1, KeyCode::KeyF2, KeyCode::KeyF3, KeyCode::KeyF4, KeyCode::KeyF5, KeyCode::KeyF6,
+        KeyCode::KeyF7, KeyCode::KeyF8, KeyCode::KeyF9, KeyCode::KeyF10, KeyCode::KeyF11, KeyCode::KeyF12,
+        KeyCode::KeyUp, KeyCode::KeyDown, KeyCode::KeyLeft, KeyCode::KeyRight, KeyCode::KeyRightCtrl,
+        KeyCode::KeyRightAlt, KeyCode::KeyLeftMeta, KeyCode::KeyRightMeta,
+    ];
+
+    /// Every combination of modifier keys.
+    fn modifier_sets() -> impl Iterator<Item = Modifiers> {
+        (0..1 << MODIFIER_NAMES.len()).map(|bits| {
+            MODIFIER_NAMES.iter().enumerate()
+                .filter(|(index, _)| bits & (1 << index) != 0)
+                .fold(Modifiers::empty(), |modifiers, (_, (flag, _, _))| modifiers | *flag)
+        })
+    }
+
+    #[test]
+    fn key_names_round_trip() {
+        for &key in KEYS {
+            assert_eq!(key.name().parse::<KeyCode>(), Ok(key));
+            assert_eq!(key.symbol().parse::<KeyCode>(), Ok(key));
+        }
+    }
+
+    #[test]
+    fn accelerators_round_trip_in_both_styles() {
+        for &key in KEYS {
+            for modifiers in modifier_sets() {
+                let hotkey = Hotkey::new(modifiers, key);
+                for style in [AcceleratorStyle::Text, AcceleratorStyle::Symbols] {
+                    let text = hotkey.format(style);
+                    assert_eq!(text.parse::<Hotkey>(), Ok(hotkey), "{:?} formatted as `{}`", style, text);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn alt_and_altgr_have_distinct_symbols() {
+        let alt = Hotkey::new(Modifiers::ALT, KeyCode::KeyK).format(AcceleratorStyle::Symbols);
+        let altgr = Hotkey::new(Modifiers::ALTGR, KeyCode::KeyK).format(AcceleratorStyle::Symbols);
+        assert_eq!(alt, "⌥K");
+        assert_eq!(altgr, "⇮K");
+    }
+
+    #[test]
+    fn symbol_errors_give_the_position() {
+        let err = "⌃⌃K".parse::<Hotkey>().unwrap_err();
+        assert_eq!((err.token(), err.position()), ("⌃", 3));
+        let err = "⌘⇧Nope".parse::<Hotkey>().unwrap_err();
+        assert_eq!((err.token(), err.position()), ("Nope", 6));
+        let err = "⌘".parse::<Hotkey>().unwrap_err();
+        assert_eq!(err.position(), 3);
+    }
+}
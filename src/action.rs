@@ -0,0 +1,596 @@
+//! Named actions bound to keys, mouse buttons and gamepads.
+
+mod file;
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::str::FromStr;
+use crate::accelerator::ParseKeyError;
+use crate::gamepad::{Gamepad, GamepadAxis, GamepadButton, Stick};
+use crate::hotkey::Hotkey;
+use crate::input_handler::{InputHandler, KeyCode, MouseButton};
+use crate::modifiers::Modifiers;
+
+pub use file::BindingsWatcher;
+
+/// A key or button an action can be bound to.
+///
+/// Bindings have a text form, used by binding files: a key name such as `Space`, an
+/// accelerator such as `Ctrl+Q`, `Mouse.Left`, `Gamepad.South` or `Gamepad.LeftZ+`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Binding {
+    Key(KeyCode),
+    /// A key pressed while exactly these modifiers are held
+    Hotkey(Hotkey),
+    Mouse(MouseButton),
+    /// A button on any connected gamepad
+    Gamepad(GamepadButton),
+    /// An axis on any connected gamepad pushed past half way in one direction, such as one
+    /// direction of a stick. Analog triggers read from `0.0` at rest to `1.0` fully pulled, so
+    /// they are only pushed in the positive direction
+    GamepadAxis { axis: GamepadAxis, positive: bool },
+}
+
+/// How far an axis must be pushed for a [`Binding::GamepadAxis`] to count as pressed.
+pub(crate) const AXIS_PRESS_THRESHOLD: f32 = 0.5;
+
+/// A source of a two-dimensional axis action, such as movement.
+///
+/// As with gamepad sticks, `x` grows to the right and `y` grows downwards. In text, four keys
+/// are written up, left, down and right, as in `W/A/S/D`, and sticks as `Gamepad.LeftStick`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AxisBinding {
+    /// Four keys, such as WASD or the arrow keys
+    Keys { up: KeyCode, down: KeyCode, left: KeyCode, right: KeyCode },
+    /// A stick on any connected gamepad
+    Stick(Stick),
+}
+
+impl AxisBinding {
+    /// Binds the W, A, S and D keys.
+    pub const WASD: AxisBinding = AxisBinding::Keys {
+        up: KeyCode::KeyW,
+        down: KeyCode::KeyS,
+        left: KeyCode::KeyA,
+        right: KeyCode::KeyD,
+    };
+
+    /// Binds the arrow keys.
+    pub const ARROWS: AxisBinding = AxisBinding::Keys {
+        up: KeyCode::KeyUp,
+        down: KeyCode::KeyDown,
+        left: KeyCode::KeyLeft,
+        right: KeyCode::KeyRight,
+    };
+}
+
+const MOUSE_BUTTON_NAMES: [(MouseButton, &str); 5] = [
+    (MouseButton::Left, "Left"),
+    (MouseButton::Right, "Right"),
+    (MouseButton::Middle, "Middle"),
+    (MouseButton::Side, "Side"),
+    (MouseButton::Extra, "Extra"),
+];
+
+const GAMEPAD_BUTTON_NAMES: [(GamepadButton, &str); 19] = [
+    (GamepadButton::South, "South"),
+    (GamepadButton::East, "East"),
+    (GamepadButton::North, "North"),
+    (GamepadButton::West, "West"),
+    (GamepadButton::C, "C"),
+    (GamepadButton::Z, "Z"),
+    (GamepadButton::LeftTrigger, "LeftTrigger"),
+    (GamepadButton::LeftTrigger2, "LeftTrigger2"),
+    (GamepadButton::RightTrigger, "RightTrigger"),
+    (GamepadButton::RightTrigger2, "RightTrigger2"),
+    (GamepadButton::Select, "Select"),
+    (GamepadButton::Start, "Start"),
+    (GamepadButton::Mode, "Mode"),
+    (GamepadButton::LeftThumb, "LeftThumb"),
+    (GamepadButton::RightThumb, "RightThumb"),
+    (GamepadButton::DPadUp, "DPadUp"),
+    (GamepadButton::DPadDown, "DPadDown"),
+    (GamepadButton::DPadLeft, "DPadLeft"),
+    (GamepadButton::DPadRight, "DPadRight"),
+];
+
+const GAMEPAD_AXIS_NAMES: [(GamepadAxis, &str); 13] = [
+    (GamepadAxis::LeftStickX, "LeftStickX"),
+    (GamepadAxis::LeftStickY, "LeftStickY"),
+    (GamepadAxis::LeftZ, "LeftZ"),
+    (GamepadAxis::RightStickX, "RightStickX"),
+    (GamepadAxis::RightStickY, "RightStickY"),
+    (GamepadAxis::RightZ, "RightZ"),
+    (GamepadAxis::Throttle, "Throttle"),
+    (GamepadAxis::Rudder, "Rudder"),
+    (GamepadAxis::Wheel, "Wheel"),
+    (GamepadAxis::Gas, "Gas"),
+    (GamepadAxis::Brake, "Brake"),
+    (GamepadAxis::DPadX, "DPadX"),
+    (GamepadAxis::DPadY, "DPadY"),
+];
+
+const STICK_NAMES: [(Stick, &str); 2] = [(Stick::Left, "LeftStick"), (Stick::Right, "RightStick")];
+
+/// Splits `Device.Name` text into the name, if the device part matches, ignoring case.
+fn strip_device<'a>(text: &'a str, device: &str) -> Option<&'a str> {
+    let (prefix, name) = text.split_once('.')?;
+    prefix.eq_ignore_ascii_case(device).then_some(name)
+}
+
+fn find_name<T: Copy>(names: &[(T, &str)], name: &str) -> Option<T> {
+    names.iter().find(|(_, known)| known.eq_ignore_ascii_case(name)).map(|(value, _)| *value)
+}
+
+fn name_of<T: PartialEq>(names: &[(T, &'static str)], value: T) -> &'static str {
+    names.iter().find(|(known, _)| *known == value).map_or("", |(_, name)| name)
+}
+
+impl fmt::Display for Binding {
+    /// Formats the binding in the form [`FromStr`] accepts.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Binding::Key(key) => f.write_str(key.name()),
+            Binding::Hotkey(hotkey) => write!(f, "{}", hotkey),
+            Binding::Mouse(button) => write!(f, "Mouse.{}", name_of(&MOUSE_BUTTON_NAMES, button)),
+            Binding::Gamepad(GamepadButton::Other(code)) => write!(f, "Gamepad.{}", code),
+            Binding::Gamepad(button) => write!(f, "Gamepad.{}", name_of(&GAMEPAD_BUTTON_NAMES, button)),
+            Binding::GamepadAxis { axis, positive } => {
+                let sign = if positive { '+' } else { '-' };
+                match axis {
+                    GamepadAxis::Other(code) => write!(f, "Gamepad.Axis{}{}", code, sign),
+                    axis => write!(f, "Gamepad.{}{}", name_of(&GAMEPAD_AXIS_NAMES, axis), sign),
+                }
+            }
+        }
+    }
+}
+
+impl FromStr for Binding {
+    type Err = ParseKeyError;
+
+    /// Parses a key name or accelerator, `Mouse.` followed by a mouse button, or `Gamepad.`
+    /// followed by a gamepad button or raw button code. A gamepad axis and direction is written
+    /// as `Gamepad.LeftStickX-`, or `Gamepad.Axis40+` for a raw axis code. Names are
+    /// case-insensitive.
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let text = text.trim();
+        if let Some(name) = strip_device(text, "Mouse") {
+            let button = find_name(&MOUSE_BUTTON_NAMES, name)
+                .ok_or_else(|| ParseKeyError::new("unknown mouse button", name, text.len() - name.len()))?;
+            return Ok(Binding::Mouse(button));
+        }
+        if let Some(name) = strip_device(text, "Gamepad")
+            && let Some((name, positive)) = name.strip_suffix('+').map(|name| (name, true))
+                .or_else(|| name.strip_suffix('-').map(|name| (name, false)))
+        {
+            let axis = find_name(&GAMEPAD_AXIS_NAMES, name)
+                .or_else(|| {
+                    let (prefix, code) = (name.get(..4)?, name.get(4..)?);
+                    prefix.eq_ignore_ascii_case("Axis").then(|| code.parse().ok().map(GamepadAxis::Other))?
+                })
+                .ok_or_else(|| ParseKeyError::new("unknown gamepad axis", name, text.len() - name.len() - 1))?;
+            return Ok(Binding::GamepadAxis { axis, positive });
+        }
+        if let Some(name) = strip_device(text, "Gamepad") {
+            let button = find_name(&GAMEPAD_BUTTON_NAMES, name)
+                .or_else(|| name.parse().ok().map(GamepadButton::Other))
+                .ok_or_else(|| ParseKeyError::new("unknown gamepad button", name, text.len() - name.len()))?;
+            return Ok(Binding::Gamepad(button));
+        }
+        let hotkey: Hotkey = text.parse()?;
+        if hotkey.modifiers.is_empty() {
+            Ok(Binding::Key(hotkey.key))
+        } else {
+            Ok(Binding::Hotkey(hotkey))
+        }
+    }
+}
+
+impl fmt::Display for AxisBinding {
+    /// Formats the binding in the form [`FromStr`] accepts.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            AxisBinding::Keys { up, down, left, right } => {
+                // `/` separates the keys, so the slash key is written by its alias.
+                let name = |key: KeyCode| if key == KeyCode::KeySlash { "Slash" } else { key.name() };
+                write!(f, "{}/{}/{}/{}", name(up), name(left), name(down), name(right))
+            }
+            AxisBinding::Stick(stick) => write!(f, "Gamepad.{}", name_of(&STICK_NAMES, stick)),
+        }
+    }
+}
+
+impl FromStr for AxisBinding {
+    type Err = ParseKeyError;
+
+    /// Parses four key names separated by `/`, in the order up, left, down and right, or
+    /// `Gamepad.LeftStick` or `Gamepad.RightStick`. `WASD` and `Arrows` are accepted as
+    /// shorthands. Names are case-insensitive.
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let text = text.trim();
+        if text.eq_ignore_ascii_case("WASD") {
+            return Ok(AxisBinding::WASD);
+        }
+        if text.eq_ignore_ascii_case("Arrows") {
+            return Ok(AxisBinding::ARROWS);
+        }
+        if let Some(name) = strip_device(text, "Gamepad") {
+            let stick = find_name(&STICK_NAMES, name)
+                .ok_or_else(|| ParseKeyError::new("unknown gamepad stick", name, text.len() - name.len()))?;
+            return Ok(AxisBinding::Stick(stick));
+        }
+
+        let mut keys = Vec::new();
+        let mut position = 0;
+        for name in text.split('/') {
+            let start = position + (name.len() - name.trim_start().len());
+            let key = name.trim().parse::<KeyCode>().map_err(|err| err.offset_by(start))?;
+            keys.push(key);
+            position += name.len() + 1;
+        }
+        let [up, left, down, right] = keys[..] else {
+            return Err(ParseKeyError::new("expected four keys separated by `/`", text, 0));
+        };
+        Ok(AxisBinding::Keys { up, down, left, right })
+    }
+}
+
+/// Maps named actions to the inputs that trigger them.
+///
+/// The map reads the input state once per [`update`](Self::update), typically once per frame,
+/// so that every query in a frame sees the same state and presses can be detected as edges.
+///
+/// ```no_run
+/// use input_query::{ActionMap, AxisBinding, Binding, GamepadButton, InputHandler, KeyCode, Stick};
+///
+/// let handler = InputHandler::new();
+/// let mut actions = ActionMap::new();
+/// actions.bind("jump", Binding::Key(KeyCode::KeySpace));
+/// actions.bind("jump", Binding::Gamepad(GamepadButton::South));
+/// actions.bind_axis("move", AxisBinding::WASD);
+/// actions.bind_axis("move", AxisBinding::Stick(Stick::Left));
+///
+/// loop {
+///     actions.update(&handler);
+///     if actions.just_pressed("jump") {
+///         println!("Jump!");
+///     }
+///     let (x, y) = actions.axis2d("move");
+///     # break;
+/// }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ActionMap {
+    bindings: HashMap<String, Vec<Binding>>,
+    axes: HashMap<String, Vec<AxisBinding>>,
+    pressed: HashSet<String>,
+    previous: HashSet<String>,
+    axis_values: HashMap<String, (f32, f32)>,
+}
+
+impl ActionMap {
+    /// Creates a map without any actions.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Binds an input to an action. An action can have several bindings and is pressed while
+    /// any of them is.
+    pub fn bind(&mut self, action: &str, binding: Binding) {
+        let bindings = self.bindings.entry(action.to_string()).or_default();
+        if !bindings.contains(&binding) {
+            bindings.push(binding);
+        }
+    }
+
+    /// Binds an axis source to a two-dimensional action. The sources of an action are added
+    /// together, up to a length of `1.0`.
+    pub fn bind_axis(&mut self, action: &str, binding: AxisBinding) {
+        let bindings = self.axes.entry(action.to_string()).or_default();
+        if !bindings.contains(&binding) {
+            bindings.push(binding);
+        }
+    }
+
+    /// Removes every binding of an action.
+    pub fn unbind(&mut self, action: &str) {
+        self.bindings.remove(action);
+        self.axes.remove(action);
+    }
+
+    /// Returns the bindings of a button action.
+    pub fn bindings(&self, action: &str) -> &[Binding] {
+        self.bindings.get(action).map_or(&[], Vec::as_slice)
+    }
+
+    /// Returns the bindings of an axis action.
+    pub fn axis_bindings(&self, action: &str) -> &[AxisBinding] {
+        self.axes.get(action).map_or(&[], Vec::as_slice)
+    }
+
+    /// Reads the current state of every bound input.
+    pub fn update(&mut self, handler: &InputHandler) {
+        self.update_from(&Snapshot::read(handler), &Mask::default());
+    }
+
+    /// Updates from a snapshot, treating masked inputs as released.
+    pub(crate) fn update_from(&mut self, state: &Snapshot, mask: &Mask) {
+        self.previous = std::mem::take(&mut self.pressed);
+        self.pressed = self.bindings.iter()
+            .filter(|(_, bindings)| bindings.iter().any(|binding| state.is_pressed(*binding, mask)))
+            .map(|(action, _)| action.clone())
+            .collect();
+        self.axis_values = self.axes.iter()
+            .map(|(action, bindings)| {
+                let (x, y) = bindings.iter()
+                    .map(|binding| state.axis(*binding, mask))
+                    .fold((0.0, 0.0), |(x, y), (dx, dy)| (x + dx, y + dy));
+                (action.clone(), clamp_length(x, y))
+            })
+            .collect();
+    }
+
+    /// Replaces every binding with those of another map, keeping the state read by earlier
+    /// updates.
+    pub fn replace_bindings(&mut self, other: ActionMap) {
+        self.bindings = other.bindings;
+        self.axes = other.axes;
+    }
+
+    /// Treats the actions pressed at the last update as already pressed before it, so none
+    /// of them count as just pressed or just released.
+    pub(crate) fn settle(&mut self) {
+        self.previous = self.pressed.clone();
+    }
+
+    /// Adds every input this map binds to a mask.
+    pub(crate) fn mask_bindings(&self, mask: &mut Mask) {
+        for binding in self.bindings.values().flatten() {
+            mask.hide(*binding);
+        }
+        for binding in self.axes.values().flatten() {
+            match *binding {
+                AxisBinding::Keys { up, down, left, right } => {
+                    mask.bindings.extend([up, down, left, right].map(Binding::Key));
+                }
+                AxisBinding::Stick(stick) => {
+                    let (x_axis, y_axis) = stick.axes();
+                    mask.axes.extend([x_axis, y_axis]);
+                }
+            }
+        }
+    }
+
+    /// Checks if a mask hides any input this map binds.
+    pub(crate) fn is_masked_by(&self, mask: &Mask) -> bool {
+        self.bindings.values().flatten().any(|binding| mask.hides(*binding))
+            || self.axes.values().flatten().any(|binding| match *binding {
+                AxisBinding::Keys { up, down, left, right } => {
+                    [up, down, left, right].into_iter().any(|key| mask.hides(Binding::Key(key)))
+                }
+                AxisBinding::Stick(stick) => {
+                    let (x_axis, y_axis) = stick.axes();
+                    mask.hides_axis(x_axis) || mask.hides_axis(y_axis)
+                }
+            })
+    }
+
+    /// Checks if any input bound to the action was held at the last update.
+    pub fn action_pressed(&self, action: &str) -> bool {
+        self.pressed.contains(action)
+    }
+
+    /// Checks if the action became pressed at the last update.
+    pub fn just_pressed(&self, action: &str) -> bool {
+        self.pressed.contains(action) && !self.previous.contains(action)
+    }
+
+    /// Checks if the action stopped being pressed at the last update.
+    pub fn just_released(&self, action: &str) -> bool {
+        !self.pressed.contains(action) && self.previous.contains(action)
+    }
+
+    /// Returns the value of a two-dimensional action at the last update, with a length of at
+    /// most `1.0`. Unknown actions are `(0.0, 0.0)`.
+    pub fn axis2d(&self, action: &str) -> (f32, f32) {
+        self.axis_values.get(action).copied().unwrap_or((0.0, 0.0))
+    }
+}
+
+fn clamp_length(x: f32, y: f32) -> (f32, f32) {
+    let length = x.hypot(y);
+    if length > 1.0 { (x / length, y / length) } else { (x, y) }
+}
+
+/// Inputs hidden from an [`ActionMap`], because a context above it consumed them.
+///
+/// A mask hides physical inputs rather than bindings. An accelerator hides its key, so masking
+/// `Ctrl+Q` also hides `Q`, but not its modifiers, which other bindings share. A gamepad axis
+/// is hidden in both directions, and a stick hides its two axes.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Mask {
+    pub(crate) all: bool,
+    /// Hidden keys, mouse buttons and gamepad buttons
+    pub(crate) bindings: HashSet<Binding>,
+    pub(crate) axes: HashSet<GamepadAxis>,
+}
+
+impl Mask {
+    /// Hides the inputs a binding reads.
+    fn hide(&mut self, binding: Binding) {
+        match binding {
+            Binding::Hotkey(hotkey) => self.bindings.insert(Binding::Key(hotkey.key)),
+            Binding::GamepadAxis { axis, .. } => self.axes.insert(axis),
+            binding => self.bindings.insert(binding),
+        };
+    }
+
+    /// Checks if an input a binding reads is hidden.
+    fn hides(&self, binding: Binding) -> bool {
+        match binding {
+            Binding::Hotkey(hotkey) => self.hides(Binding::Key(hotkey.key)),
+            Binding::GamepadAxis { axis, .. } => self.hides_axis(axis),
+            binding => self.all || self.bindings.contains(&binding),
+        }
+    }
+
+    fn hides_axis(&self, axis: GamepadAxis) -> bool {
+        self.all || self.axes.contains(&axis)
+    }
+}
+
+/// Where a [`Snapshot`] reads inputs from, so tests can stand in for the handler.
+pub(crate) trait InputSource {
+    fn is_pressed(&self, key: KeyCode) -> bool;
+    fn is_mouse_pressed(&self, button: MouseButton) -> bool;
+    fn modifiers(&self) -> Modifiers;
+    fn gamepads(&self) -> Vec<Gamepad>;
+}
+
+impl InputSource for InputHandler {
+    fn is_pressed(&self, key: KeyCode) -> bool {
+        InputHandler::is_pressed(self, key)
+    }
+
+    fn is_mouse_pressed(&self, button: MouseButton) -> bool {
+        InputHandler::is_mouse_pressed(self, button)
+    }
+
+    fn modifiers(&self) -> Modifiers {
+        InputHandler::modifiers(self)
+    }
+
+    fn gamepads(&self) -> Vec<Gamepad> {
+        InputHandler::gamepads(self)
+    }
+}
+
+/// The input state an [`ActionMap`] reads in one update.
+pub(crate) struct Snapshot<'a> {
+    source: &'a dyn InputSource,
+    modifiers: Modifiers,
+    gamepads: Vec<Gamepad>,
+}
+
+impl<'a> Snapshot<'a> {
+    pub(crate) fn read(source: &'a dyn InputSource) -> Self {
+        Snapshot {
+            source,
+            modifiers: source.modifiers(),
+            gamepads: source.gamepads(),
+        }
+    }
+
+    fn is_pressed(&self, binding: Binding, mask: &Mask) -> bool {
+        if mask.hides(binding) {
+            return false;
+        }
+        match binding {
+            Binding::Key(key) => self.source.is_pressed(key),
+            Binding::Hotkey(hotkey) => {
+                hotkey.matches(hotkey.key, self.modifiers) && self.is_pressed(Binding::Key(hotkey.key), mask)
+            }
+            Binding::Mouse(button) => self.source.is_mouse_pressed(button),
+            Binding::Gamepad(button) => self.gamepads.iter().any(|gamepad| gamepad.is_pressed(button)),
+            Binding::GamepadAxis { axis, positive } => self.gamepads.iter()
+                .flat_map(|gamepad| gamepad.binding_axes())
+                .filter(|(a, _)| *a == axis)
+                .any(|(_, value)| if positive { value } else { -value } >= AXIS_PRESS_THRESHOLD),
+        }
+    }
+
+    fn axis(&self, binding: AxisBinding, mask: &Mask) -> (f32, f32) {
+        match binding {
+            AxisBinding::Keys { up, down, left, right } => {
+                let pressed = |key: KeyCode| self.is_pressed(Binding::Key(key), mask) as i32 as f32;
+                let value = |negative: KeyCode, positive: KeyCode| pressed(positive) - pressed(negative);
+                clamp_length(value(left, right), value(up, down))
+            }
+            AxisBinding::Stick(stick) => {
+                let (x_axis, y_axis) = stick.axes();
+                let read = |gamepad: &Gamepad, axis| if mask.hides_axis(axis) { 0.0 } else { gamepad.axis(axis) };
+                self.gamepads.iter()
+                    .map(|gamepad| (read(gamepad, x_axis), read(gamepad, y_axis)))
+                    .max_by(|a, b| a.0.hypot(a.1).total_cmp(&b.0.hypot(b.1)))
+                    .unwrap_or((0.0, 0.0))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(binding: Binding) {
+        let text = binding.to_string();
+        assert_eq!(text.parse::<Binding>(), Ok(binding), "{}", text);
+    }
+
+    #[test]
+    fn bindings_round_trip() {
+        for key in [KeyCode::KeySpace, KeyCode::KeyQ, KeyCode::KeySlash, KeyCode::KeyF12, KeyCode::KeyLeftShift] {
+            round_trip(Binding::Key(key));
+        }
+        round_trip(Binding::Hotkey(Hotkey::new(Modifiers::CTRL, KeyCode::KeyQ)));
+        round_trip(Binding::Hotkey(Hotkey::new(Modifiers::ALT | Modifiers::SUPER, KeyCode::KeyF4)));
+        round_trip(Binding::Hotkey(Hotkey::new(Modifiers::CTRL, KeyCode::KeyLeftShift)));
+        for (button, _) in MOUSE_BUTTON_NAMES {
+            round_trip(Binding::Mouse(button));
+        }
+        for (button, _) in GAMEPAD_BUTTON_NAMES {
+            round_trip(Binding::Gamepad(button));
+        }
+        round_trip(Binding::Gamepad(GamepadButton::Other(0x2c0)));
+        for axis in GAMEPAD_AXIS_NAMES.map(|(axis, _)| axis).into_iter().chain([GamepadAxis::Other(40)]) {
+            round_trip(Binding::GamepadAxis { axis, positive: true });
+            round_trip(Binding::GamepadAxis { axis, positive: false });
+        }
+    }
+
+    #[test]
+    fn binding_text_forms() {
+        let axis = |axis, positive| Ok(Binding::GamepadAxis { axis, positive });
+        assert_eq!("Gamepad.LeftZ+".parse(), axis(GamepadAxis::LeftZ, true));
+        assert_eq!(" gamepad.axis7- ".parse(), axis(GamepadAxis::Other(7), false));
+        assert_eq!("mouse.side".parse(), Ok(Binding::Mouse(MouseButton::Side)));
+        assert_eq!("GAMEPAD.south".parse(), Ok(Binding::Gamepad(GamepadButton::South)));
+        assert_eq!("Gamepad.300".parse(), Ok(Binding::Gamepad(GamepadButton::Other(300))));
+        // Modifier-free accelerators are plain keys.
+        assert_eq!("Space".parse(), Ok(Binding::Key(KeyCode::KeySpace)));
+        assert_eq!(
+            Binding::GamepadAxis { axis: GamepadAxis::RightStickY, positive: false }.to_string(),
+            "Gamepad.RightStickY-",
+        );
+    }
+
+    #[test]
+    fn binding_errors() {
+        let error = |text: &str| text.parse::<Binding>().unwrap_err().to_string();
+        assert_eq!(error("Mouse.Fourth"), "unknown mouse button `Fourth` at position 6");
+        assert_eq!(error("Gamepad.Nope"), "unknown gamepad button `Nope` at position 8");
+        assert_eq!(error("Gamepad.Nope+"), "unknown gamepad axis `Nope` at position 8");
+        assert_eq!(error("Gamepad.Axis+"), "unknown gamepad axis `Axis` at position 8");
+    }
+
+    #[test]
+    fn axis_bindings_round_trip() {
+        let bindings = [
+            AxisBinding::WASD,
+            AxisBinding::ARROWS,
+            AxisBinding::Keys {
+                up: KeyCode::KeySlash, down: KeyCode::KeyK, left: KeyCode::KeyJ, right: KeyCode::KeyL,
+            },
+            AxisBinding::Stick(Stick::Left),
+            AxisBinding::Stick(Stick::Right),
+        ];
+        for binding in bindings {
+            let text = binding.to_string();
+            assert_eq!(text.parse::<AxisBinding>(), Ok(binding), "{}", text);
+        }
+        assert_eq!("wasd".parse(), Ok(AxisBinding::WASD));
+        let error = "W/A/S".parse::<AxisBinding>().unwrap_err();
+        assert_eq!(error.to_string(), "expected four keys separated by `/` `W/A/S` at position 0");
+    }
+}
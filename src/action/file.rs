@@ -0,0 +1,476 @@
+//! Binding files, in a subset of TOML, and watching them for changes.
+
+use std::collections::HashSet;
+use std::collections::hash_map::DefaultHasher;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use super::{ActionMap, AxisBinding, Binding};
+
+impl ActionMap {
+    /// Parses bindings from the text of a binding file.
+    ///
+    /// Each line binds an action to one input or an array of them, in their text form:
+    ///
+    /// ```toml
+    /// # Comments start with `#`
+    /// jump = ["Space", "Gamepad.South"]
+    /// quit = "Ctrl+Q"
+    /// move = ["WASD", "Gamepad.LeftStick"]
+    /// ```
+    ///
+    /// Values that parse as an [`AxisBinding`] bind a two-dimensional action, and others a
+    /// [`Binding`]; an action can't mix both. Each action may only appear once, even with an
+    /// empty array. Only this subset of TOML is supported: tables, numbers and other kinds of
+    /// values are rejected. If anything is invalid, the error names the offending line.
+    pub fn parse(text: &str) -> io::Result<ActionMap> {
+        let mut actions = ActionMap::new();
+        let mut parser = Parser { text, position: 0, line: 1 };
+        let mut seen = HashSet::new();
+        loop {
+            parser.skip_blank_lines();
+            let Some(c) = parser.peek() else {
+                break;
+            };
+            if c == '[' {
+                return Err(parser.error("tables are not supported"));
+            }
+            let line = parser.line;
+            let action = parser.key()?;
+            if !seen.insert(action.clone()) {
+                return Err(invalid(line, &format!("action `{}` is bound twice", action)));
+            }
+            parser.skip_spaces();
+            parser.expect('=')?;
+            parser.skip_spaces();
+            let values = parser.value()?;
+            parser.end_of_line()?;
+
+            let mix_error = |line| invalid(line, &format!("action `{}` mixes axis and button bindings", action));
+            for (value, line) in values {
+                if let Ok(binding) = value.parse::<AxisBinding>() {
+                    if !actions.bindings(&action).is_empty() {
+                        return Err(mix_error(line));
+                    }
+                    actions.bind_axis(&action, binding);
+                } else {
+                    let binding = value.parse::<Binding>().map_err(|err| invalid(line, &err.to_string()))?;
+                    if !actions.axis_bindings(&action).is_empty() {
+                        return Err(mix_error(line));
+                    }
+                    actions.bind(&action, binding);
+                }
+            }
+        }
+        Ok(actions)
+    }
+
+    /// Loads bindings from a binding file. Errors name the file, and the line for invalid
+    /// contents.
+    pub fn load(path: impl AsRef<Path>) -> io::Result<ActionMap> {
+        let path = path.as_ref();
+        fs::read_to_string(path)
+            .and_then(|text| ActionMap::parse(&text))
+            .map_err(|err| with_path(path, err))
+    }
+}
+
+fn with_path(path: &Path, err: io::Error) -> io::Error {
+    io::Error::new(err.kind(), format!("{}: {}", path.display(), err))
+}
+
+fn invalid(line: usize, message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("line {}: {}", line, message))
+}
+
+/// Reads the TOML subset used by binding files, keeping track of the line.
+struct Parser<'a> {
+    text: &'a str,
+    position: usize,
+    line: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<char> {
+        self.text[self.position..].chars().next()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.position += c.len_utf8();
+        if c == '\n' {
+            self.line += 1;
+        }
+        Some(c)
+    }
+
+    fn error(&self, message: &str) -> io::Error {
+        invalid(self.line, message)
+    }
+
+    fn expect(&mut self, expected: char) -> io::Result<()> {
+        match self.peek() {
+            Some(c) if c == expected => {
+                self.bump();
+                Ok(())
+            }
+            _ => Err(self.error(&format!("expected `{}`", expected))),
+        }
+    }
+
+    fn skip_spaces(&mut self) {
+        while matches!(self.peek(), Some(' ' | '\t')) {
+            self.bump();
+        }
+    }
+
+    fn skip_comment(&mut self) {
+        if self.peek() == Some('#') {
+            while !matches!(self.peek(), None | Some('\n')) {
+                self.bump();
+            }
+        }
+    }
+
+    /// Skips whitespace, line breaks and comments.
+    fn skip_blank_lines(&mut self) {
+        loop {
+            self.skip_spaces();
+            self.skip_comment();
+            match self.peek() {
+                Some('\r' | '\n') => {
+                    self.bump();
+                }
+                _ => break,
+            }
+        }
+    }
+
+    /// Skips to the next line, allowing only a comment before it.
+    fn end_of_line(&mut self) -> io::Result<()> {
+        self.skip_spaces();
+        self.skip_comment();
+        if self.peek() == Some('\r') {
+            self.bump();
+        }
+        match self.bump() {
+            None | Some('\n') => Ok(()),
+            Some(_) => Err(self.error("expected the end of the line")),
+        }
+    }
+
+    /// Reads a bare or quoted action name.
+    fn key(&mut self) -> io::Result<String> {
+        if matches!(self.peek(), Some('"' | '\'')) {
+            return self.string();
+        }
+        let start = self.position;
+        while self.peek().is_some_and(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+            self.bump();
+        }
+        if start == self.position {
+            return Err(self.error("expected an action name"));
+        }
+        Ok(self.text[start..self.position].to_string())
+    }
+
+    /// Reads a string, or an array of strings that may span several lines, and returns each
+    /// string with the line it is on.
+    fn value(&mut self) -> io::Result<Vec<(String, usize)>> {
+        if self.peek() != Some('[') {
+            let line = self.line;
+            return Ok(vec![(self.string()?, line)]);
+        }
+        self.bump();
+        let mut values = Vec::new();
+        loop {
+            self.skip_blank_lines();
+            if self.peek() == Some(']') {
+                self.bump();
+                return Ok(values);
+            }
+            let line = self.line;
+            values.push((self.string()?, line));
+            self.skip_blank_lines();
+            match self.bump() {
+                Some(',') => {}
+                Some(']') => return Ok(values),
+                _ => return Err(invalid(self.line, "expected `,` or `]`")),
+            }
+        }
+    }
+
+    /// Reads a basic `"..."` or literal `'...'` string.
+    fn string(&mut self) -> io::Result<String> {
+        let quote = match self.peek() {
+            Some(quote @ ('"' | '\'')) => quote,
+            _ => return Err(self.error("expected a string")),
+        };
+        self.bump();
+        let mut value = String::new();
+        loop {
+            let c = match self.peek() {
+                None | Some('\n') => return Err(self.error("unterminated string")),
+                Some(c) => c,
+            };
+            self.bump();
+            match c {
+                c if c == quote => return Ok(value),
+                '\\' if quote == '"' => match self.bump() {
+                    Some('\\') => value.push('\\'),
+                    Some('"') => value.push('"'),
+                    Some('t') => value.push('\t'),
+                    _ => return Err(self.error("unsupported escape sequence")),
+                },
+                c => value.push(c),
+            }
+        }
+    }
+}
+
+/// Watches a binding file and reloads it when it changes, so controls can be tweaked while a
+/// game runs.
+///
+/// Each poll only checks the file's modification time and size, and reads the file when
+/// either changed. Saving it without edits doesn't reload it. When the new contents are
+/// invalid, the error is returned and the bindings in use can simply be kept.
+///
+/// ```no_run
+/// use input_query::{ActionMap, BindingsWatcher, InputHandler};
+///
+/// let handler = InputHandler::new();
+/// let mut watcher = BindingsWatcher::new("controls.toml");
+/// let mut actions = ActionMap::new();
+/// loop {
+///     match watcher.poll() {
+///         Some(Ok(bindings)) => actions.replace_bindings(bindings),
+///         Some(Err(err)) => eprintln!("keeping previous bindings: {}", err),
+///         None => {}
+///     }
+///     actions.update(&handler);
+///     # break;
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct BindingsWatcher {
+    path: PathBuf,
+    /// File seen by the last poll, which is `None` if the file couldn't be read
+    seen: Option<Option<FileStamp>>,
+}
+
+/// What identifies a version of the watched file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct FileStamp {
+    modified: Option<SystemTime>,
+    len: u64,
+    /// Hash of the contents
+    hash: u64,
+}
+
+impl BindingsWatcher {
+    /// Creates a watcher for a file. The first poll loads it.
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        BindingsWatcher { path: path.into(), seen: None }
+    }
+
+    /// Returns the watched file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Loads the file if it changed since the last poll, or returns `None` if it didn't.
+    ///
+    /// A file that can't be read, for example because it was removed, is reported once until
+    /// it changes again.
+    pub fn poll(&mut self) -> Option<io::Result<ActionMap>> {
+        let metadata = match fs::metadata(&self.path) {
+            Ok(metadata) => metadata,
+            Err(err) => return self.unreadable(err),
+        };
+        let modified = metadata.modified().ok();
+        let len = metadata.len();
+        let previous = self.seen.flatten();
+        if previous.is_some_and(|stamp| stamp.modified == modified && stamp.len == len) {
+            return None;
+        }
+        let text = match fs::read_to_string(&self.path) {
+            Ok(text) => text,
+            Err(err) => return self.unreadable(err),
+        };
+        let mut hasher = DefaultHasher::new();
+        text.hash(&mut hasher);
+        let hash = hasher.finish();
+        self.seen = Some(Some(FileStamp { modified, len, hash }));
+        if previous.is_some_and(|stamp| stamp.hash == hash) {
+            return None;
+        }
+        Some(ActionMap::parse(&text).map_err(|err| with_path(&self.path, err)))
+    }
+
+    /// Reports a file that can't be read, unless the last poll already did.
+    fn unreadable(&mut self, err: io::Error) -> Option<io::Result<ActionMap>> {
+        if self.seen == Some(None) {
+            return None;
+        }
+        self.seen = Some(None);
+        Some(Err(with_path(&self.path, err)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gamepad::{GamepadAxis, GamepadButton, Stick};
+    use crate::hotkey::Hotkey;
+    use crate::input_handler::{KeyCode, MouseButton};
+    use crate::modifiers::Modifiers;
+
+    fn error(text: &str) -> String {
+        let err = ActionMap::parse(text).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        err.to_string()
+    }
+
+    #[test]
+    fn parses_strings_arrays_and_comments() {
+        let actions = ActionMap::parse(concat!(
+            "# Controls\n",
+            "\n",
+            "jump = [\"Space\", 'Gamepad.South'] # either\n",
+            "\"quit game\" = \"Ctrl+Q\"\r\n",
+            "move = \"WASD\"\n",
+            "none = []",
+        )).unwrap();
+        assert_eq!(actions.bindings("jump"), [Binding::Key(KeyCode::KeySpace), Binding::Gamepad(GamepadButton::South)]);
+        assert_eq!(actions.bindings("quit game"), [Binding::Hotkey(Hotkey::new(Modifiers::CTRL, KeyCode::KeyQ))]);
+        assert_eq!(actions.axis_bindings("move"), [AxisBinding::WASD]);
+        assert!(actions.bindings("none").is_empty());
+    }
+
+    #[test]
+    fn arrays_span_lines() {
+        let actions = ActionMap::parse(concat!(
+            "fire = [\n",
+            "    \"Mouse.Left\", # primary\n",
+            "\n",
+            "    \"Gamepad.RightTrigger2\",\n",
+            "]\n",
+            "look = [\"Arrows\",\n",
+            "        \"Gamepad.RightStick\"]\n",
+        )).unwrap();
+        assert_eq!(actions.bindings("fire"), [
+            Binding::Mouse(MouseButton::Left), Binding::Gamepad(GamepadButton::RightTrigger2),
+        ]);
+        assert_eq!(actions.axis_bindings("look"), [AxisBinding::ARROWS, AxisBinding::Stick(Stick::Right)]);
+    }
+
+    #[test]
+    fn errors_name_the_line() {
+        assert_eq!(error("jump = \"Space\"\n\nfire = \"Nope\""), "line 3: unknown key `Nope` at position 0");
+        assert_eq!(error("jump = \"Space\"\nfire \"Mouse.Left\""), "line 2: expected `=`");
+        assert_eq!(error("jump = \"Space\" \"Enter\""), "line 1: expected the end of the line");
+        assert_eq!(error("jump = Space"), "line 1: expected a string");
+        assert_eq!(error("= \"Space\""), "line 1: expected an action name");
+        assert_eq!(error("jump = \"Space"), "line 1: unterminated string");
+        assert_eq!(error("jump = \"Space\nfire = \"Enter\""), "line 1: unterminated string");
+    }
+
+    #[test]
+    fn errors_inside_arrays_name_the_line_of_the_value() {
+        assert_eq!(error("jump = [\n  \"Space\",\n  \"Nope\",\n]"), "line 3: unknown key `Nope` at position 0");
+        assert_eq!(error("jump = [\n  \"Space\"\n  \"Enter\"\n]"), "line 3: expected `,` or `]`");
+        assert_eq!(error("jump = [\n  \"Space\",\n"), "line 3: expected a string");
+    }
+
+    #[test]
+    fn rejects_mixed_axis_and_button_bindings() {
+        assert_eq!(error("move = [\"WASD\", \"Space\"]"), "line 1: action `move` mixes axis and button bindings");
+        assert_eq!(
+            error("move = [\n  \"Space\",\n  \"Gamepad.LeftStick\",\n]"),
+            "line 3: action `move` mixes axis and button bindings",
+        );
+    }
+
+    #[test]
+    fn rejects_repeated_actions() {
+        assert_eq!(error("jump = \"Space\"\njump = \"Enter\""), "line 2: action `jump` is bound twice");
+        assert_eq!(error("jump = []\n\"jump\" = \"Enter\""), "line 2: action `jump` is bound twice");
+        // A repeated action is rejected before its bindings are checked against each other.
+        assert_eq!(error("move = \"WASD\"\nmove = \"Space\""), "line 2: action `move` is bound twice");
+    }
+
+    #[test]
+    fn rejects_tables() {
+        assert_eq!(error("jump = \"Space\"\n[player2]\njump = \"Enter\""), "line 2: tables are not supported");
+        assert_eq!(error("[[players]]"), "line 1: tables are not supported");
+        assert_eq!(error("jump = { key = \"Space\" }"), "line 1: expected a string");
+        assert_eq!(error("player.jump = \"Space\""), "line 1: expected `=`");
+    }
+
+    #[test]
+    fn string_escapes() {
+        let actions = ActionMap::parse(concat!(
+            "\"say \\\"hi\\\"\" = \"H\"\n",
+            "'C:\\path' = 'P'\n",
+            "\"tab\\there\" = \"T\"\n",
+            "\"back\\\\slash\" = \"B\"\n",
+        )).unwrap();
+        assert_eq!(actions.bindings("say \"hi\""), [Binding::Key(KeyCode::KeyH)]);
+        assert_eq!(actions.bindings("C:\\path"), [Binding::Key(KeyCode::KeyP)]);
+        assert_eq!(actions.bindings("tab\there"), [Binding::Key(KeyCode::KeyT)]);
+        assert_eq!(actions.bindings("back\\slash"), [Binding::Key(KeyCode::KeyB)]);
+        assert_eq!(error("jump = \"Sp\\nace\""), "line 1: unsupported escape sequence");
+    }
+
+    #[test]
+    fn displayed_bindings_parse_back() {
+        let bindings = [
+            Binding::Key(KeyCode::KeySlash),
+            Binding::Hotkey(Hotkey::new(Modifiers::CTRL | Modifiers::SHIFT, KeyCode::KeyS)),
+            Binding::Mouse(MouseButton::Middle),
+            Binding::Gamepad(GamepadButton::Other(0x2c0)),
+            Binding::GamepadAxis { axis: GamepadAxis::LeftStickX, positive: false },
+            Binding::GamepadAxis { axis: GamepadAxis::Other(40), positive: true },
+        ];
+        let axis_bindings = [
+            AxisBinding::Keys {
+                up: KeyCode::KeyI, down: KeyCode::KeyK, left: KeyCode::KeyJ, right: KeyCode::KeySlash,
+            },
+            AxisBinding::Stick(Stick::Left),
+        ];
+        let quoted = |values: Vec<String>| {
+            values.iter().map(|value| format!("{:?}", value)).collect::<Vec<_>>().join(", ")
+        };
+        let text = format!(
+            "buttons = [{}]\naxes = [{}]\n",
+            quoted(bindings.iter().map(Binding::to_string).collect()),
+            quoted(axis_bindings.iter().map(AxisBinding::to_string).collect()),
+        );
+        let actions = ActionMap::parse(&text).unwrap();
+        assert_eq!(actions.bindings("buttons"), bindings);
+        assert_eq!(actions.axis_bindings("axes"), axis_bindings);
+    }
+
+    #[test]
+    fn watcher_reloads_changed_files() {
+        let path = std::env::temp_dir().join(format!("input_query_watcher_{}.toml", std::process::id()));
+        let _ = fs::remove_file(&path);
+        let mut watcher = BindingsWatcher::new(&path);
+        assert!(watcher.poll().unwrap().is_err());
+        assert!(watcher.poll().is_none());
+
+        fs::write(&path, "jump = \"Space\"\n").unwrap();
+        let actions = watcher.poll().unwrap().unwrap();
+        assert_eq!(actions.bindings("jump"), [Binding::Key(KeyCode::KeySpace)]);
+        assert!(watcher.poll().is_none());
+
+        fs::write(&path, "jump = \"Enter\", oops\n").unwrap();
+        assert!(watcher.poll().unwrap().is_err());
+        assert!(watcher.poll().is_none());
+
+        fs::remove_file(&path).unwrap();
+        assert!(watcher.poll().unwrap().is_err());
+    }
+}
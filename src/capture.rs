@@ -0,0 +1,293 @@
+//! Capturing the next input, for "press a key to bind" prompts.
+
+use crate::action::AXIS_PRESS_THRESHOLD;
+// The rest of the file is only used by the Linux backend so far.
+#[cfg(any(target_os = "linux", test))]
+use {
+    std::collections::HashSet,
+    crate::action::Binding,
+    crate::gamepad::{Gamepad, GamepadAxis, GamepadButton},
+    crate::hotkey::Hotkey,
+    crate::input_handler::{KeyCode, MouseButton},
+    crate::modifiers::Modifiers,
+};
+
+/// Which inputs [`InputHandler::capture_next`](crate::InputHandler::capture_next) captures.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CaptureFilter {
+    /// Capture keys, and accelerators such as `Ctrl+Q`
+    pub keys: bool,
+    /// Capture mouse buttons
+    pub mouse: bool,
+    /// Capture gamepad buttons
+    pub gamepad_buttons: bool,
+    /// Capture gamepad axes
+    pub gamepad_axes: bool,
+    /// How far an axis must be pushed from rest to be captured, above `0.0` and up to `1.0`.
+    /// Other values are clamped to that range.
+    pub axis_threshold: f32,
+}
+
+impl Default for CaptureFilter {
+    fn default() -> Self {
+        CaptureFilter {
+            keys: true,
+            mouse: true,
+            gamepad_buttons: true,
+            gamepad_axes: true,
+            axis_threshold: AXIS_PRESS_THRESHOLD,
+        }
+    }
+}
+
+/// The inputs held at one point during a capture.
+#[cfg(any(target_os = "linux", test))]
+#[derive(Debug, Clone, Default)]
+pub(crate) struct CaptureState {
+    pub(crate) keys: Vec<KeyCode>,
+    pub(crate) mouse: Vec<MouseButton>,
+    /// Buttons held on any gamepad
+    pub(crate) gamepad_buttons: Vec<GamepadButton>,
+    /// Axis values of every gamepad, as bindings read them
+    pub(crate) gamepad_axes: Vec<(GamepadAxis, f32)>,
+}
+
+#[cfg(any(target_os = "linux", test))]
+impl CaptureState {
+    /// Replaces the gamepad inputs with those of `gamepads`.
+    ///
+    /// Triggers read from `0.0` at rest, as they do for a [`Binding::GamepadAxis`], so a
+    /// released trigger isn't captured as pushed the other way.
+    pub(crate) fn set_gamepads(&mut self, gamepads: &[Gamepad]) {
+        self.gamepad_buttons = gamepads.iter()
+            .flat_map(|gamepad| gamepad.pressed_buttons().iter().copied())
+            .collect();
+        self.gamepad_axes = gamepads.iter().flat_map(|gamepad| gamepad.binding_axes()).collect();
+    }
+}
+
+/// Finds the first input that goes down after a capture starts, from successive states.
+///
+/// Inputs held when the capture starts are ignored until they are released, and axes until
+/// they return to rest. A modifier key is captured when it is released without another key
+/// being pressed; otherwise it becomes part of a [`Binding::Hotkey`].
+#[cfg(any(target_os = "linux", test))]
+#[derive(Debug, Clone)]
+pub(crate) struct Capture {
+    filter: CaptureFilter,
+    ignored_keys: HashSet<KeyCode>,
+    ignored_mouse: HashSet<MouseButton>,
+    ignored_buttons: HashSet<GamepadButton>,
+    ignored_axes: HashSet<(GamepadAxis, bool)>,
+    /// Modifier keys pressed since the capture started, which are still held
+    modifiers: Vec<KeyCode>,
+}
+
+#[cfg(any(target_os = "linux", test))]
+impl Capture {
+    pub(crate) fn new(mut filter: CaptureFilter, initial: &CaptureState) -> Self {
+        // An axis always at least `0.0` from rest would be captured immediately.
+        filter.axis_threshold = filter.axis_threshold.clamp(f32::MIN_POSITIVE, 1.0);
+        Capture {
+            filter,
+            ignored_keys: initial.keys.iter().copied().collect(),
+            ignored_mouse: initial.mouse.iter().copied().collect(),
+            ignored_buttons: initial.gamepad_buttons.iter().copied().collect(),
+            ignored_axes: Self::pushed_axes(filter, initial).map(|(axis, positive, _)| (axis, positive)).collect(),
+            modifiers: Vec::new(),
+        }
+    }
+
+    /// Axes pushed past the threshold, with their direction and how far they are pushed.
+    fn pushed_axes(filter: CaptureFilter, state: &CaptureState) -> impl Iterator<Item = (GamepadAxis, bool, f32)> + '_ {
+        state.gamepad_axes.iter()
+            .filter(move |(_, value)| value.abs() >= filter.axis_threshold)
+            .map(|&(axis, value)| (axis, value > 0.0, value.abs()))
+    }
+
+    /// Returns the captured binding once an input goes down.
+    pub(crate) fn check(&mut self, state: &CaptureState) -> Option<Binding> {
+        self.ignored_keys.retain(|key| state.keys.contains(key));
+        self.ignored_mouse.retain(|button| state.mouse.contains(button));
+        self.ignored_buttons.retain(|button| state.gamepad_buttons.contains(button));
+        let pushed: Vec<_> = Self::pushed_axes(self.filter, state).collect();
+        self.ignored_axes.retain(|ignored| pushed.iter().any(|(axis, positive, _)| *ignored == (*axis, *positive)));
+
+        if self.filter.keys {
+            if let Some(released) = self.modifiers.iter().find(|key| !state.keys.contains(key)) {
+                return Some(Binding::Key(*released));
+            }
+            let new_keys = state.keys.iter().filter(|key| !self.ignored_keys.contains(key));
+            for &key in new_keys {
+                if Modifiers::from_key(key).is_empty() {
+                    let modifiers = self.modifiers.iter()
+                        .fold(Modifiers::empty(), |modifiers, key| modifiers | Modifiers::from_key(*key));
+                    return Some(if modifiers.is_empty() {
+                        Binding::Key(key)
+                    } else {
+                        Binding::Hotkey(Hotkey::new(modifiers, key))
+                    });
+                }
+                if !self.modifiers.contains(&key) {
+                    self.modifiers.push(key);
+                }
+            }
+        }
+        if self.filter.mouse
+            && let Some(button) = state.mouse.iter().find(|button| !self.ignored_mouse.contains(button))
+        {
+            return Some(Binding::Mouse(*button));
+        }
+        if self.filter.gamepad_buttons
+            && let Some(button) = state.gamepad_buttons.iter().find(|button| !self.ignored_buttons.contains(button))
+        {
+            return Some(Binding::Gamepad(*button));
+        }
+        if self.filter.gamepad_axes {
+            return pushed.into_iter()
+                .filter(|(axis, positive, _)| !self.ignored_axes.contains(&(*axis, *positive)))
+                .max_by(|a, b| a.2.total_cmp(&b.2))
+                .map(|(axis, positive, _)| Binding::GamepadAxis { axis, positive });
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gamepad::{GamepadId, GamepadIdentity};
+
+    fn keys(keys: &[KeyCode]) -> CaptureState {
+        CaptureState { keys: keys.to_vec(), ..Default::default() }
+    }
+
+    fn axes(axes: &[(GamepadAxis, f32)]) -> CaptureState {
+        CaptureState { gamepad_axes: axes.to_vec(), ..Default::default() }
+    }
+
+    fn gamepad(axes: &[(GamepadAxis, f32)], triggers: [Option<f32>; 2]) -> Gamepad {
+        Gamepad {
+            id: GamepadId(0),
+            identity: GamepadIdentity { bus_type: 3, vendor_id: 0, product_id: 0, version: 0, name: String::new() },
+            mapping_name: None,
+            buttons: Vec::new(),
+            axes: axes.to_vec(),
+            triggers,
+        }
+    }
+
+    #[test]
+    fn captures_keys_and_accelerators() {
+        let mut capture = Capture::new(CaptureFilter::default(), &keys(&[]));
+        assert_eq!(capture.check(&keys(&[KeyCode::KeyA])), Some(Binding::Key(KeyCode::KeyA)));
+
+        let mut capture = Capture::new(CaptureFilter::default(), &keys(&[]));
+        assert_eq!(capture.check(&keys(&[KeyCode::KeyLeftCtrl])), None);
+        assert_eq!(capture.check(&keys(&[KeyCode::KeyLeftCtrl, KeyCode::KeyLeftShift])), None);
+        assert_eq!(
+            capture.check(&keys(&[KeyCode::KeyLeftCtrl, KeyCode::KeyLeftShift, KeyCode::KeyQ])),
+            Some(Binding::Hotkey(Hotkey::new(Modifiers::CTRL | Modifiers::SHIFT, KeyCode::KeyQ))),
+        );
+    }
+
+    #[test]
+    fn captures_a_modifier_released_alone() {
+        let mut capture = Capture::new(CaptureFilter::default(), &keys(&[]));
+        assert_eq!(capture.check(&keys(&[KeyCode::KeyRightAlt])), None);
+        assert_eq!(capture.check(&keys(&[])), Some(Binding::Key(KeyCode::KeyRightAlt)));
+    }
+
+    #[test]
+    fn ignores_inputs_held_at_the_start_until_released() {
+        let held = CaptureState {
+            keys: vec![KeyCode::KeyEnter],
+            mouse: vec![MouseButton::Left],
+            gamepad_buttons: vec![GamepadButton::South],
+            gamepad_axes: vec![(GamepadAxis::LeftStickX, 0.9)],
+        };
+        let mut capture = Capture::new(CaptureFilter::default(), &held);
+        assert_eq!(capture.check(&held), None);
+        assert_eq!(capture.check(&CaptureState::default()), None);
+        assert_eq!(capture.check(&keys(&[KeyCode::KeyEnter])), Some(Binding::Key(KeyCode::KeyEnter)));
+
+        let mut capture = Capture::new(CaptureFilter::default(), &held);
+        assert_eq!(capture.check(&axes(&[(GamepadAxis::LeftStickX, 0.2)])), None);
+        assert_eq!(
+            capture.check(&axes(&[(GamepadAxis::LeftStickX, 0.9)])),
+            Some(Binding::GamepadAxis { axis: GamepadAxis::LeftStickX, positive: true }),
+        );
+    }
+
+    #[test]
+    fn captures_mouse_and_gamepad_buttons() {
+        let mut capture = Capture::new(CaptureFilter::default(), &CaptureState::default());
+        let state = CaptureState { mouse: vec![MouseButton::Right], ..Default::default() };
+        assert_eq!(capture.check(&state), Some(Binding::Mouse(MouseButton::Right)));
+
+        let mut capture = Capture::new(CaptureFilter::default(), &CaptureState::default());
+        let state = CaptureState { gamepad_buttons: vec![GamepadButton::North], ..Default::default() };
+        assert_eq!(capture.check(&state), Some(Binding::Gamepad(GamepadButton::North)));
+    }
+
+    #[test]
+    fn filtered_inputs_are_not_captured() {
+        let filter = CaptureFilter { keys: false, mouse: false, ..Default::default() };
+        let mut capture = Capture::new(filter, &CaptureState::default());
+        let state = CaptureState {
+            keys: vec![KeyCode::KeyA],
+            mouse: vec![MouseButton::Left],
+            ..Default::default()
+        };
+        assert_eq!(capture.check(&state), None);
+        let state = CaptureState { gamepad_buttons: vec![GamepadButton::East], ..state };
+        assert_eq!(capture.check(&state), Some(Binding::Gamepad(GamepadButton::East)));
+    }
+
+    #[test]
+    fn captures_the_axis_pushed_furthest_past_the_threshold() {
+        let filter = CaptureFilter { axis_threshold: 0.6, ..Default::default() };
+        let mut capture = Capture::new(filter, &CaptureState::default());
+        let state = axes(&[(GamepadAxis::LeftStickX, 0.55), (GamepadAxis::LeftStickY, -0.3)]);
+        assert_eq!(capture.check(&state), None);
+        assert_eq!(
+            capture.check(&axes(&[(GamepadAxis::LeftStickX, 0.7), (GamepadAxis::LeftStickY, -0.8)])),
+            Some(Binding::GamepadAxis { axis: GamepadAxis::LeftStickY, positive: false }),
+        );
+    }
+
+    #[test]
+    fn a_zero_threshold_does_not_capture_axes_at_rest() {
+        let filter = CaptureFilter { axis_threshold: 0.0, ..Default::default() };
+        let mut capture = Capture::new(filter, &CaptureState::default());
+        assert_eq!(capture.check(&axes(&[(GamepadAxis::RightStickX, 0.0)])), None);
+        assert_eq!(
+            capture.check(&axes(&[(GamepadAxis::RightStickX, 0.01)])),
+            Some(Binding::GamepadAxis { axis: GamepadAxis::RightStickX, positive: true }),
+        );
+    }
+
+    #[test]
+    fn released_triggers_are_not_captured() {
+        let idle = [(GamepadAxis::LeftZ, -1.0), (GamepadAxis::RightZ, -1.0)];
+        let mut state = CaptureState::default();
+        state.set_gamepads(&[gamepad(&idle, [Some(0.0), Some(0.0)])]);
+        let mut capture = Capture::new(CaptureFilter::default(), &state);
+        assert_eq!(capture.check(&state), None);
+
+        let pulled = [(GamepadAxis::LeftZ, -1.0), (GamepadAxis::RightZ, 0.6)];
+        state.set_gamepads(&[gamepad(&pulled, [Some(0.0), Some(0.8)])]);
+        let pulled = Binding::GamepadAxis { axis: GamepadAxis::RightZ, positive: true };
+        assert_eq!(capture.check(&state), Some(pulled));
+    }
+
+    #[test]
+    fn z_axes_without_triggers_are_read_as_they_are() {
+        let mut state = CaptureState::default();
+        state.set_gamepads(&[gamepad(&[(GamepadAxis::LeftZ, 0.0)], [None, None])]);
+        let mut capture = Capture::new(CaptureFilter::default(), &state);
+        state.set_gamepads(&[gamepad(&[(GamepadAxis::LeftZ, -0.9)], [None, None])]);
+        let pushed = Binding::GamepadAxis { axis: GamepadAxis::LeftZ, positive: false };
+        assert_eq!(capture.check(&state), Some(pushed));
+    }
+}
@@ -0,0 +1,433 @@
+//! Stacks of input contexts, such as gameplay under a pause menu.
+
+use std::collections::{HashMap, HashSet};
+use crate::action::{ActionMap, Mask, Snapshot};
+use crate::input_handler::InputHandler;
+
+/// A set of actions that are active together, such as those of gameplay, a menu or a text field.
+#[derive(Debug, Clone, Default)]
+pub struct InputContext {
+    /// Bindings of the context's actions
+    pub actions: ActionMap,
+    /// Let contexts below see the inputs this context doesn't bind. Otherwise they see none.
+    pub passthrough: bool,
+}
+
+impl InputContext {
+    /// Creates a context with the given bindings, which consumes every input.
+    pub fn new(actions: ActionMap) -> Self {
+        InputContext { actions, passthrough: false }
+    }
+
+    /// Sets whether contexts below see the inputs this context doesn't bind.
+    pub fn with_passthrough(mut self, passthrough: bool) -> Self {
+        self.passthrough = passthrough;
+        self
+    }
+
+    /// Adds the inputs this context hides from the contexts below it to a mask.
+    fn mask(&self, mask: &mut Mask) {
+        if self.passthrough {
+            self.actions.mask_bindings(mask);
+        } else {
+            mask.all = true;
+        }
+    }
+}
+
+/// A stack of named input contexts, where upper contexts consume inputs before lower ones.
+///
+/// Each update, contexts are read from the top of the stack down. A context that isn't
+/// passthrough hides every input from the contexts below it, so a pause menu pushed over
+/// gameplay swallows WASD. A passthrough context, such as a HUD overlay, only hides the inputs
+/// it binds. Hidden inputs read as released, so actions held when a context is pushed over them
+/// are reported as just released.
+///
+/// Inputs are hidden as physical keys, buttons and axes rather than as bindings. A context
+/// binding `Ctrl+Q` hides the Q key, so `Q` and `Shift+Q` below it are hidden too, but not Ctrl,
+/// which other accelerators share. A context binding a stick hides that stick's axes, both
+/// from sticks and from axis bindings such as `Gamepad.LeftStickX+`, and the other way around.
+///
+/// Inputs held when a context is pushed, or revealed by a pop, count as already pressed in
+/// that context rather than just pressed, so the key that opened a menu doesn't also close it.
+/// Only the contexts the removed context hid inputs from are affected by a pop.
+///
+/// ```no_run
+/// use input_query::{ActionMap, AxisBinding, Binding, ContextStack, InputContext, InputHandler, KeyCode};
+///
+/// let mut gameplay = ActionMap::new();
+/// gameplay.bind_axis("move", AxisBinding::WASD);
+/// gameplay.bind("pause", Binding::Key(KeyCode::KeyEsc));
+/// let mut menu = ActionMap::new();
+/// menu.bind("resume", Binding::Key(KeyCode::KeyEsc));
+///
+/// let handler = InputHandler::new();
+/// let mut contexts = ContextStack::new();
+/// contexts.add_context("gameplay", InputContext::new(gameplay));
+/// contexts.add_context("menu", InputContext::new(menu));
+/// contexts.push("gameplay");
+/// loop {
+///     contexts.update(&handler);
+///     if contexts.just_pressed("pause") {
+///         contexts.push("menu");
+///     } else if contexts.just_pressed("resume") {
+///         contexts.pop();
+///     }
+///     let (x, y) = contexts.axis2d("move");
+///     # break;
+/// }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ContextStack {
+    contexts: HashMap<String, InputContext>,
+    /// Names of the active contexts, from the bottom up
+    stack: Vec<String>,
+    /// Contexts that started seeing inputs since the last update, whose held actions don't
+    /// count as just pressed
+    settling: HashSet<String>,
+}
+
+impl ContextStack {
+    /// Creates a stack without any contexts.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a context under a name, replacing any context of the same name.
+    pub fn add_context(&mut self, name: &str, context: InputContext) {
+        self.contexts.insert(name.to_string(), context);
+    }
+
+    /// Unregisters a context, removing it from the stack, and returns it.
+    pub fn remove_context(&mut self, name: &str) -> Option<InputContext> {
+        if let Some(index) = self.stack.iter().position(|active| active == name) {
+            self.stack.remove(index);
+            self.reveal(name, index);
+        }
+        self.contexts.remove(name)
+    }
+
+    /// Returns a registered context.
+    pub fn context(&self, name: &str) -> Option<&InputContext> {
+        self.contexts.get(name)
+    }
+
+    /// Returns a registered context, to change its bindings.
+    pub fn context_mut(&mut self, name: &str) -> Option<&mut InputContext> {
+        self.contexts.get_mut(name)
+    }
+
+    /// Activates a context on top of the stack, moving it there if it was already active.
+    /// Returns `false` if no context has this name.
+    pub fn push(&mut self, name: &str) -> bool {
+        if !self.contexts.contains_key(name) {
+            return false;
+        }
+        self.stack.retain(|active| active != name);
+        self.stack.push(name.to_string());
+        self.settling.insert(name.to_string());
+        true
+    }
+
+    /// Deactivates the top context and returns its name.
+    pub fn pop(&mut self) -> Option<String> {
+        let popped = self.stack.pop()?;
+        self.reveal(&popped, self.stack.len());
+        Some(popped)
+    }
+
+    /// Settles the contexts below `index` that the context `name` hid inputs from, since they
+    /// see those inputs again.
+    fn reveal(&mut self, name: &str, index: usize) {
+        let Some(context) = self.contexts.get(name) else {
+            return;
+        };
+        let mut mask = Mask::default();
+        context.mask(&mut mask);
+        let revealed = self.stack[..index].iter()
+            .filter(|below| self.contexts.get(*below).is_some_and(|below| below.actions.is_masked_by(&mask)));
+        self.settling.extend(revealed.cloned());
+    }
+
+    /// Returns the name of the top context.
+    pub fn top(&self) -> Option<&str> {
+        self.stack.last().map(String::as_str)
+    }
+
+    /// Returns the names of the active contexts, from the bottom up.
+    pub fn active(&self) -> &[String] {
+        &self.stack
+    }
+
+    /// Reads the current input state into every active context.
+    pub fn update(&mut self, handler: &InputHandler) {
+        self.update_from(&Snapshot::read(handler));
+    }
+
+    fn update_from(&mut self, state: &Snapshot) {
+        let mut mask = Mask::default();
+        for name in self.stack.iter().rev() {
+            let Some(context) = self.contexts.get_mut(name) else {
+                continue;
+            };
+            context.actions.update_from(state, &mask);
+            if self.settling.contains(name) {
+                context.actions.settle();
+            }
+            context.mask(&mut mask);
+        }
+        self.settling.clear();
+    }
+
+    /// Checks if the action was held at the last update, in the topmost active context that
+    /// binds it.
+    pub fn action_pressed(&self, action: &str) -> bool {
+        self.find(action).is_some_and(|actions| actions.action_pressed(action))
+    }
+
+    /// Checks if the action became pressed at the last update, in the topmost active context
+    /// that binds it.
+    pub fn just_pressed(&self, action: &str) -> bool {
+        self.find(action).is_some_and(|actions| actions.just_pressed(action))
+    }
+
+    /// Checks if the action stopped being pressed at the last update, in the topmost active
+    /// context that binds it.
+    pub fn just_released(&self, action: &str) -> bool {
+        self.find(action).is_some_and(|actions| actions.just_released(action))
+    }
+
+    /// Returns the value of a two-dimensional action at the last update, in the topmost active
+    /// context that binds it.
+    pub fn axis2d(&self, action: &str) -> (f32, f32) {
+        self.find(action).map_or((0.0, 0.0), |actions| actions.axis2d(action))
+    }
+
+    fn find(&self, action: &str) -> Option<&ActionMap> {
+        self.stack.iter().rev()
+            .filter_map(|name| self.contexts.get(name))
+            .map(|context| &context.actions)
+            .find(|actions| !actions.bindings(action).is_empty() || !actions.axis_bindings(action).is_empty())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::action::{AxisBinding, Binding, InputSource};
+    use crate::gamepad::{Gamepad, GamepadAxis, GamepadId, GamepadIdentity, Stick};
+    use crate::hotkey::Hotkey;
+    use crate::input_handler::{KeyCode, MouseButton};
+    use crate::modifiers::Modifiers;
+
+    /// Inputs held during one update.
+    #[derive(Default)]
+    struct Held {
+        keys: Vec<KeyCode>,
+        axes: Vec<(GamepadAxis, f32)>,
+    }
+
+    impl InputSource for Held {
+        fn is_pressed(&self, key: KeyCode) -> bool {
+            self.keys.contains(&key)
+        }
+
+        fn is_mouse_pressed(&self, _button: MouseButton) -> bool {
+            false
+        }
+
+        fn modifiers(&self) -> Modifiers {
+            Modifiers::held(|key| self.keys.contains(&key))
+        }
+
+        fn gamepads(&self) -> Vec<Gamepad> {
+            vec![Gamepad {
+                id: GamepadId(0),
+                identity: GamepadIdentity {
+                    bus_type: 3, vendor_id: 0, product_id: 0, version: 0, name: String::new(),
+                },
+                mapping_name: None,
+                buttons: Vec::new(),
+                axes: self.axes.clone(),
+                triggers: [None; 2],
+            }]
+        }
+    }
+
+    fn keys(keys: &[KeyCode]) -> Held {
+        Held { keys: keys.to_vec(), ..Default::default() }
+    }
+
+    fn update(contexts: &mut ContextStack, held: &Held) {
+        contexts.update_from(&Snapshot::read(held));
+    }
+
+    fn context(bindings: &[(&str, Binding)]) -> InputContext {
+        let mut actions = ActionMap::new();
+        for (action, binding) in bindings {
+            actions.bind(action, *binding);
+        }
+        InputContext::new(actions)
+    }
+
+    /// Gameplay bound to Esc and Space, under a menu bound to Esc and Enter.
+    fn menu_over_gameplay() -> ContextStack {
+        let mut contexts = ContextStack::new();
+        contexts.add_context("gameplay", context(&[
+            ("pause", Binding::Key(KeyCode::KeyEsc)),
+            ("jump", Binding::Key(KeyCode::KeySpace)),
+        ]));
+        contexts.add_context("menu", context(&[
+            ("resume", Binding::Key(KeyCode::KeyEsc)),
+            ("select", Binding::Key(KeyCode::KeyEnter)),
+        ]));
+        contexts.push("gameplay");
+        contexts
+    }
+
+    #[test]
+    fn push_and_pop() {
+        let mut contexts = menu_over_gameplay();
+        assert!(!contexts.push("missing"));
+        assert!(contexts.push("menu"));
+        assert_eq!(contexts.active(), ["gameplay", "menu"]);
+        assert_eq!(contexts.top(), Some("menu"));
+        // Pushing an active context moves it to the top.
+        assert!(contexts.push("gameplay"));
+        assert_eq!(contexts.active(), ["menu", "gameplay"]);
+        assert_eq!(contexts.pop().as_deref(), Some("gameplay"));
+        assert_eq!(contexts.pop().as_deref(), Some("menu"));
+        assert_eq!(contexts.pop(), None);
+    }
+
+    #[test]
+    fn the_top_context_hides_every_input() {
+        let mut contexts = menu_over_gameplay();
+        update(&mut contexts, &keys(&[]));
+        update(&mut contexts, &keys(&[KeyCode::KeySpace]));
+        assert!(contexts.just_pressed("jump"));
+
+        contexts.push("menu");
+        update(&mut contexts, &keys(&[KeyCode::KeySpace]));
+        assert!(!contexts.action_pressed("jump"));
+        assert!(contexts.just_released("jump"));
+    }
+
+    #[test]
+    fn the_key_that_pushed_a_context_is_not_just_pressed_in_it() {
+        let mut contexts = menu_over_gameplay();
+        update(&mut contexts, &keys(&[]));
+        update(&mut contexts, &keys(&[KeyCode::KeyEsc]));
+        assert!(contexts.just_pressed("pause"));
+        contexts.push("menu");
+        update(&mut contexts, &keys(&[KeyCode::KeyEsc]));
+        assert!(contexts.action_pressed("resume"));
+        assert!(!contexts.just_pressed("resume"));
+
+        update(&mut contexts, &keys(&[]));
+        update(&mut contexts, &keys(&[KeyCode::KeyEsc]));
+        assert!(contexts.just_pressed("resume"));
+        contexts.pop();
+        update(&mut contexts, &keys(&[KeyCode::KeyEsc]));
+        assert!(contexts.action_pressed("pause"));
+        assert!(!contexts.just_pressed("pause"));
+    }
+
+    #[test]
+    fn passthrough_contexts_only_hide_what_they_bind() {
+        let mut contexts = menu_over_gameplay();
+        contexts.add_context("hud", context(&[("map", Binding::Key(KeyCode::KeyM))]).with_passthrough(true));
+        contexts.context_mut("gameplay").unwrap().actions.bind("map", Binding::Key(KeyCode::KeyTab));
+        contexts.push("hud");
+        update(&mut contexts, &keys(&[]));
+        update(&mut contexts, &keys(&[KeyCode::KeySpace, KeyCode::KeyM]));
+        assert!(contexts.just_pressed("jump"));
+        assert!(contexts.just_pressed("map"));
+    }
+
+    #[test]
+    fn accelerators_hide_their_key_but_not_their_modifiers() {
+        let mut contexts = ContextStack::new();
+        contexts.add_context("game", context(&[
+            ("quick_save", Binding::Key(KeyCode::KeyQ)),
+            ("save", Binding::Hotkey(Hotkey::new(Modifiers::CTRL, KeyCode::KeyS))),
+        ]));
+        let quit = Binding::Hotkey(Hotkey::new(Modifiers::CTRL, KeyCode::KeyQ));
+        contexts.add_context("overlay", context(&[("quit", quit)]).with_passthrough(true));
+        contexts.push("game");
+        contexts.push("overlay");
+
+        update(&mut contexts, &keys(&[KeyCode::KeyQ]));
+        assert!(!contexts.action_pressed("quick_save"));
+        update(&mut contexts, &keys(&[KeyCode::KeyLeftCtrl, KeyCode::KeyQ]));
+        assert!(contexts.action_pressed("quit"));
+        update(&mut contexts, &keys(&[KeyCode::KeyLeftCtrl, KeyCode::KeyS]));
+        assert!(contexts.action_pressed("save"));
+    }
+
+    #[test]
+    fn sticks_and_axis_bindings_hide_each_other() {
+        let mut stick = ActionMap::new();
+        stick.bind_axis("look", AxisBinding::Stick(Stick::Right));
+        let mut axis = ActionMap::new();
+        axis.bind("turn", Binding::GamepadAxis { axis: GamepadAxis::RightStickX, positive: true });
+        let held = Held {
+            axes: vec![(GamepadAxis::RightStickX, 0.8), (GamepadAxis::RightStickY, 0.4)],
+            ..Default::default()
+        };
+
+        let mut contexts = ContextStack::new();
+        contexts.add_context("stick", InputContext::new(stick.clone()));
+        contexts.add_context("axis", InputContext::new(axis.clone()).with_passthrough(true));
+        contexts.push("stick");
+        contexts.push("axis");
+        update(&mut contexts, &held);
+        assert!(contexts.action_pressed("turn"));
+        // Only the hidden X axis reads as released.
+        assert_eq!(contexts.axis2d("look"), (0.0, 0.4));
+
+        let mut contexts = ContextStack::new();
+        contexts.add_context("axis", InputContext::new(axis));
+        contexts.add_context("stick", InputContext::new(stick).with_passthrough(true));
+        contexts.push("axis");
+        contexts.push("stick");
+        update(&mut contexts, &held);
+        assert_eq!(contexts.axis2d("look"), (0.8, 0.4));
+        assert!(!contexts.action_pressed("turn"));
+    }
+
+    #[test]
+    fn popping_only_settles_the_contexts_that_were_hidden() {
+        let mut contexts = menu_over_gameplay();
+        contexts.add_context("hud", context(&[("map", Binding::Key(KeyCode::KeyM))]).with_passthrough(true));
+        contexts.context_mut("gameplay").unwrap().actions.bind("map", Binding::Key(KeyCode::KeyM));
+        contexts.add_context("chat", context(&[("send", Binding::Key(KeyCode::KeyEnter))]).with_passthrough(true));
+        contexts.push("hud");
+        contexts.push("chat");
+        update(&mut contexts, &keys(&[]));
+
+        // Chat hid nothing gameplay or the HUD binds, so a jump pressed as it closes is new.
+        contexts.pop();
+        update(&mut contexts, &keys(&[KeyCode::KeySpace]));
+        assert!(contexts.just_pressed("jump"));
+
+        // The HUD hid M from gameplay, so M held as it closes is already pressed there.
+        update(&mut contexts, &keys(&[KeyCode::KeySpace, KeyCode::KeyM]));
+        contexts.pop();
+        update(&mut contexts, &keys(&[KeyCode::KeySpace, KeyCode::KeyM]));
+        assert!(contexts.action_pressed("map"));
+        assert!(!contexts.just_pressed("map"));
+    }
+
+    #[test]
+    fn removing_a_context_reveals_the_ones_below() {
+        let mut contexts = menu_over_gameplay();
+        contexts.push("menu");
+        update(&mut contexts, &keys(&[KeyCode::KeySpace]));
+        assert!(contexts.remove_context("menu").is_some());
+        assert_eq!(contexts.active(), ["gameplay"]);
+        update(&mut contexts, &keys(&[KeyCode::KeySpace]));
+        assert!(contexts.action_pressed("jump"));
+        assert!(!contexts.just_pressed("jump"));
+    }
+}
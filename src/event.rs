@@ -0,0 +1,100 @@
+//! Input events delivered to subscribers of an [`InputHandler`](crate::InputHandler).
+
+use std::time::{Duration, Instant};
+use crate::gesture::Gesture;
+use crate::input_handler::{KeyCode, MouseButton};
+use crate::modifiers::Modifiers;
+use crate::switch::SwitchKind;
+use crate::touch::TouchDeviceId;
+
+/// Whether a key went down or up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum KeyState {
+    Pressed,
+    Released,
+    /// The key is still held and auto-repeated.
+    Repeat,
+}
+
+/// How held keys auto-repeat.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct KeyRepeat {
+    /// How long a key must be held before it starts repeating
+    pub delay: Duration,
+    /// Time between repeats
+    pub period: Duration,
+}
+
+/// The kernel clock that device timestamps are taken from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum EventClock {
+    /// Wall-clock time, which jumps when the system time is changed
+    #[default]
+    Realtime,
+    /// Time since boot, which never jumps
+    Monotonic,
+}
+
+/// When an event happened, according to the kernel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct EventTime {
+    pub(crate) clock: EventClock,
+    pub(crate) timestamp: Duration,
+    pub(crate) instant: Instant,
+}
+
+impl EventTime {
+    /// Returns the clock the timestamp was taken from.
+    pub fn clock(&self) -> EventClock {
+        self.clock
+    }
+
+    /// Returns the timestamp as the kernel reported it: time since the Unix epoch for
+    /// [`EventClock::Realtime`], or since boot for [`EventClock::Monotonic`].
+    pub fn timestamp(&self) -> Duration {
+        self.timestamp
+    }
+
+    /// Returns the timestamp as an `Instant`, comparable with `Instant::now()`.
+    ///
+    /// The conversion is made when the event is read. With [`EventClock::Realtime`] it is off
+    /// by however much the system time was changed since the event happened.
+    pub fn instant(&self) -> Instant {
+        self.instant
+    }
+
+    /// Returns the time `duration` after this one, for events generated later.
+    pub(crate) fn after(self, duration: Duration) -> EventTime {
+        EventTime {
+            timestamp: self.timestamp + duration,
+            instant: self.instant + duration,
+            ..self
+        }
+    }
+}
+
+/// An input event, as seen by the background thread.
+#[derive(Debug, Clone, PartialEq)]
+pub enum InputEvent {
+    /// A key was pressed, released or repeated. `modifiers` is the modifier state after the
+    /// event.
+    Key { key: KeyCode, state: KeyState, modifiers: Modifiers, time: EventTime },
+    /// A mouse button was pressed or released.
+    MouseButton { button: MouseButton, state: KeyState, time: EventTime },
+    /// A touchpad recognized a gesture.
+    Gesture { device: TouchDeviceId, gesture: Gesture, time: EventTime },
+    /// A switch, such as a laptop lid, changed state.
+    Switch { switch: SwitchKind, active: bool, time: EventTime },
+}
+
+impl InputEvent {
+    /// Returns when the event happened.
+    pub fn time(&self) -> EventTime {
+        match *self {
+            InputEvent::Key { time, .. }
+            | InputEvent::MouseButton { time, .. }
+            | InputEvent::Gesture { time, .. }
+            | InputEvent::Switch { time, .. } => time,
+        }
+    }
+}
@@ -0,0 +1,249 @@
+//! Platform-independent gamepad and joystick types.
+//!
+//! Gamepads are currently only read by the Linux backend, which picks them up from the same
+//! evdev devices it uses for keyboards.
+
+mod calibration;
+pub(crate) mod mapping;
+mod response;
+
+pub use calibration::{AxisCalibration, Calibration, CalibrationStore};
+pub use mapping::{GamepadGuid, GamepadMapping, MappingDb};
+pub use response::{
+    AxisSettings, GamepadSettings, ResponseCurve, Stick, StickSettings, Trigger, TriggerSettings,
+};
+
+/// Identifies a connected gamepad or joystick.
+///
+/// Ids are assigned when the [`InputHandler`](crate::InputHandler) is created and stay valid
+/// for its lifetime.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct GamepadId(pub(crate) usize);
+
+/// Identifies a model of gamepad across runs, as opposed to [`GamepadId`] which only
+/// identifies a connected device.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct GamepadIdentity {
+    /// Bus the device is connected through (USB, Bluetooth, ...)
+    pub bus_type: u16,
+    /// Vendor id reported by the device
+    pub vendor_id: u16,
+    /// Product id reported by the device
+    pub product_id: u16,
+    /// Product version reported by the device
+    pub version: u16,
+    /// Name reported by the device
+    pub name: String,
+}
+
+/// A gamepad button in the standard layout.
+///
+/// The face buttons are named by position rather than by label, so `South` is "A" on an
+/// Xbox pad and "Cross" on a PlayStation pad.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GamepadButton {
+    /// Bottom face button
+    South,
+    /// Right face button
+    East,
+    /// Top face button
+    North,
+    /// Left face button
+    West,
+    /// Extra face buttons found on some older pads
+    C,
+    Z,
+    /// Left shoulder button
+    LeftTrigger,
+    /// Left trigger, when reported as a button
+    LeftTrigger2,
+    /// Right shoulder button
+    RightTrigger,
+    /// Right trigger, when reported as a button
+    RightTrigger2,
+    /// Select/Back/Share button
+    Select,
+    /// Start/Options button
+    Start,
+    /// Guide/Home button
+    Mode,
+    /// Left stick click
+    LeftThumb,
+    /// Right stick click
+    RightThumb,
+    /// D-pad buttons
+    DPadUp,
+    DPadDown,
+    DPadLeft,
+    DPadRight,
+    /// A button outside the standard layout, identified by its raw platform code
+    Other(u16),
+}
+
+/// An analog gamepad or joystick axis.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GamepadAxis {
+    /// Left stick axes
+    LeftStickX,
+    LeftStickY,
+    /// Left trigger, or the Z axis of a joystick
+    LeftZ,
+    /// Right stick axes
+    RightStickX,
+    RightStickY,
+    /// Right trigger, or the Z rotation of a joystick
+    RightZ,
+    /// Flight stick axes
+    Throttle,
+    Rudder,
+    Wheel,
+    Gas,
+    Brake,
+    /// D-pad reported as a hat switch
+    DPadX,
+    DPadY,
+    /// An axis outside the standard layout, identified by its raw platform code
+    Other(u16),
+}
+
+/// Range information for a raw axis, as reported by the device.
+#[cfg(target_os = "linux")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct AxisInfo {
+    pub(crate) minimum: i32,
+    pub(crate) maximum: i32,
+    pub(crate) flat: i32,
+}
+
+#[cfg(target_os = "linux")]
+impl AxisInfo {
+    /// Maps a raw value onto `-1.0..=1.0`, treating the device's flat range around the
+    /// center as zero and rescaling the rest so the output stays continuous.
+    ///
+    /// A calibration, if given, replaces the advertised range, and each side of its center
+    /// is scaled separately.
+    pub(crate) fn normalize(&self, raw: i32, calibration: Option<&AxisCalibration>) -> f32 {
+        let (minimum, center, maximum) = match calibration {
+            Some(calibration) => (
+                calibration.minimum as f32,
+                calibration.center as f32,
+                calibration.maximum as f32,
+            ),
+            None => {
+                let minimum = self.minimum as f32;
+                let maximum = self.maximum as f32;
+                (minimum, (minimum + maximum) / 2.0, maximum)
+            }
+        };
+
+        let offset = raw as f32 - center;
+        let half = if offset < 0.0 { center - minimum } else { maximum - center };
+        if half <= 0.0 {
+            return 0.0;
+        }
+        let flat = (self.flat.max(0) as f32).min(half);
+        if offset.abs() <= flat {
+            return 0.0;
+        }
+        if flat >= half {
+            return offset.signum();
+        }
+        let scaled = (offset.abs() - flat) / (half - flat);
+        (scaled * offset.signum()).clamp(-1.0, 1.0)
+    }
+}
+
+/// A snapshot of a gamepad's state at the time it was queried.
+#[derive(Debug, Clone)]
+pub struct Gamepad {
+    pub(crate) id: GamepadId,
+    pub(crate) identity: GamepadIdentity,
+    pub(crate) mapping_name: Option<String>,
+    pub(crate) buttons: Vec<GamepadButton>,
+    pub(crate) axes: Vec<(GamepadAxis, f32)>,
+    /// Analog trigger values, which are `None` if the device has no analog triggers
+    pub(crate) triggers: [Option<f32>; 2],
+}
+
+impl Gamepad {
+    /// Returns the id of this gamepad.
+    pub fn id(&self) -> GamepadId {
+        self.id
+    }
+
+    /// Returns the name reported by the device.
+    pub fn name(&self) -> &str {
+        &self.identity.name
+    }
+
+    /// Returns the USB vendor id reported by the device.
+    pub fn vendor_id(&self) -> u16 {
+        self.identity.vendor_id
+    }
+
+    /// Returns the USB product id reported by the device.
+    pub fn product_id(&self) -> u16 {
+        self.identity.product_id
+    }
+
+    /// Returns the identity of the device, used to look up stored calibrations.
+    pub fn identity(&self) -> &GamepadIdentity {
+        &self.identity
+    }
+
+    /// Returns the SDL GUID of the device.
+    pub fn guid(&self) -> GamepadGuid {
+        GamepadGuid::from_identity(&self.identity)
+    }
+
+    /// Returns the name of the mapping used to translate the device's buttons and axes into
+    /// the standard layout, or `None` if the device is reported as-is.
+    pub fn mapping_name(&self) -> Option<&str> {
+        self.mapping_name.as_deref()
+    }
+
+    /// Checks if a button was pressed when the snapshot was taken.
+    ///
+    /// D-pads reported as a hat switch are also reported as the four `DPad` buttons, and
+    /// analog triggers as `LeftTrigger2` and `RightTrigger2` according to their
+    /// [`TriggerSettings`].
+    pub fn is_pressed(&self, button: GamepadButton) -> bool {
+        self.buttons.contains(&button)
+    }
+
+    /// Returns the buttons that were pressed when the snapshot was taken.
+    pub fn pressed_buttons(&self) -> &[GamepadButton] {
+        &self.buttons
+    }
+
+    /// Returns the value of an axis, normalised to `-1.0..=1.0`.
+    ///
+    /// Calibration and the gamepad's [`GamepadSettings`] have already been applied. Axes the
+    /// device does not have read as `0.0`.
+    pub fn axis(&self, axis: GamepadAxis) -> f32 {
+        self.axes
+            .iter()
+            .find(|(a, _)| *a == axis)
+            .map_or(0.0, |(_, value)| *value)
+    }
+
+    /// Returns every axis the device reports, with its normalised value.
+    pub fn axes(&self) -> &[(GamepadAxis, f32)] {
+        &self.axes
+    }
+
+    /// Returns how far a trigger is pulled, from `0.0` at rest to `1.0` fully pulled.
+    pub fn trigger(&self, trigger: Trigger) -> f32 {
+        self.triggers[trigger as usize].unwrap_or(0.0)
+    }
+
+    /// Returns every axis with the value a [`Binding`](crate::Binding) reads: analog triggers
+    /// read as [`trigger`](Self::trigger), from `0.0` at rest, and other axes as
+    /// [`axis`](Self::axis).
+    pub(crate) fn binding_axes(&self) -> impl Iterator<Item = (GamepadAxis, f32)> + '_ {
+        self.axes.iter().map(|&(axis, value)| {
+            let trigger = [Trigger::Left, Trigger::Right].into_iter().find(|trigger| trigger.axis() == axis);
+            (axis, trigger.and_then(|trigger| self.triggers[trigger as usize]).unwrap_or(value))
+        })
+    }
+}
@@ -0,0 +1,237 @@
+//! Axis calibration and its persistence.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use super::GamepadIdentity;
+
+/// The observed range of a single raw axis.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AxisCalibration {
+    /// Smallest value the axis reached
+    pub minimum: i32,
+    /// Value of the axis at rest
+    pub center: i32,
+    /// Largest value the axis reached
+    pub maximum: i32,
+}
+
+#[cfg(target_os = "linux")]
+impl AxisCalibration {
+    pub(crate) fn starting_at(value: i32) -> Self {
+        AxisCalibration {
+            minimum: value,
+            center: value,
+            maximum: value,
+        }
+    }
+
+    pub(crate) fn record(&mut self, value: i32) {
+        self.minimum = self.minimum.min(value);
+        self.maximum = self.maximum.max(value);
+    }
+}
+
+/// Calibrated ranges for the axes of one gamepad, keyed by raw axis code.
+///
+/// Calibration replaces the range the device advertises, which is often wider than what the
+/// stick can physically reach.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Calibration {
+    axes: HashMap<u16, AxisCalibration>,
+}
+
+impl Calibration {
+    /// Creates an empty calibration.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the calibrated range of a raw axis.
+    pub fn set_axis(&mut self, code: u16, axis: AxisCalibration) {
+        self.axes.insert(code, axis);
+    }
+
+    /// Returns the calibrated range of a raw axis.
+    pub fn axis(&self, code: u16) -> Option<&AxisCalibration> {
+        self.axes.get(&code)
+    }
+
+    /// Returns `true` if no axis has been calibrated.
+    pub fn is_empty(&self) -> bool {
+        self.axes.is_empty()
+    }
+
+    /// Iterates over the calibrated axes and their raw codes.
+    pub fn iter(&self) -> impl Iterator<Item = (u16, &AxisCalibration)> {
+        self.axes.iter().map(|(code, axis)| (*code, axis))
+    }
+}
+
+/// Calibrations for several devices, stored by device identity.
+///
+/// The store is saved as plain text, with one section per device:
+///
+/// ```text
+/// [0003:045e:028e:0114] Microsoft X-Box 360 pad
+/// 0 = -32768 -120 32767
+/// 1 = -31002 310 32767
+/// ```
+///
+/// Each axis line holds the raw axis code followed by its minimum, center and maximum.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CalibrationStore {
+    devices: HashMap<GamepadIdentity, Calibration>,
+}
+
+impl CalibrationStore {
+    /// Creates an empty store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Loads a store from a file.
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::parse(&fs::read_to_string(path)?)
+    }
+
+    /// Saves the store to a file, replacing its contents.
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        fs::write(path, self.to_string())
+    }
+
+    /// Parses a store from its text form.
+    pub fn parse(text: &str) -> io::Result<Self> {
+        let mut store = CalibrationStore::new();
+        let mut current: Option<GamepadIdentity> = None;
+
+        for (index, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let invalid = |message: &str| {
+                io::Error::new(io::ErrorKind::InvalidData, format!("line {}: {}", index + 1, message))
+            };
+
+            if let Some(rest) = line.strip_prefix('[') {
+                let (ids, name) = rest.split_once(']').ok_or_else(|| invalid("unterminated device header"))?;
+                let ids: Vec<u16> = ids.split(':')
+                    .map(|id| u16::from_str_radix(id, 16))
+                    .collect::<Result<_, _>>()
+                    .map_err(|_| invalid("device ids must be hexadecimal"))?;
+                let [bus_type, vendor_id, product_id, version] = ids[..] else {
+                    return Err(invalid("expected bus:vendor:product:version"));
+                };
+                let identity = GamepadIdentity {
+                    bus_type,
+                    vendor_id,
+                    product_id,
+                    version,
+                    name: name.trim().to_string(),
+                };
+                store.devices.entry(identity.clone()).or_default();
+                current = Some(identity);
+                continue;
+            }
+
+            let identity = current.as_ref().ok_or_else(|| invalid("axis entry before any device header"))?;
+            let (code, range) = line.split_once('=').ok_or_else(|| invalid("expected `code = min center max`"))?;
+            let code = code.trim().parse().map_err(|_| invalid("invalid axis code"))?;
+            let values: Vec<i32> = range.split_whitespace()
+                .map(str::parse)
+                .collect::<Result<_, _>>()
+                .map_err(|_| invalid("invalid axis range"))?;
+            let [minimum, center, maximum] = values[..] else {
+                return Err(invalid("expected `code = min center max`"));
+            };
+            store.devices.entry(identity.clone()).or_default()
+                .set_axis(code, AxisCalibration { minimum, center, maximum });
+        }
+
+        Ok(store)
+    }
+
+    /// Stores the calibration for a device, replacing any previous one.
+    pub fn insert(&mut self, identity: GamepadIdentity, calibration: Calibration) {
+        self.devices.insert(identity, calibration);
+    }
+
+    /// Returns the calibration stored for a device.
+    pub fn get(&self, identity: &GamepadIdentity) -> Option<&Calibration> {
+        self.devices.get(identity)
+    }
+
+    /// Removes the calibration stored for a device.
+    pub fn remove(&mut self, identity: &GamepadIdentity) -> Option<Calibration> {
+        self.devices.remove(identity)
+    }
+}
+
+impl fmt::Display for CalibrationStore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut devices: Vec<_> = self.devices.iter().collect();
+        devices.sort_by(|a, b| a.0.cmp(b.0));
+
+        for (identity, calibration) in devices {
+            writeln!(
+                f,
+                "[{:04x}:{:04x}:{:04x}:{:04x}] {}",
+                identity.bus_type, identity.vendor_id, identity.product_id, identity.version, identity.name
+            )?;
+            let mut axes: Vec<_> = calibration.iter().collect();
+            axes.sort_by_key(|(code, _)| *code);
+            for (code, axis) in axes {
+                writeln!(f, "{} = {} {} {}", code, axis.minimum, axis.center, axis.maximum)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn identity(name: &str) -> GamepadIdentity {
+        GamepadIdentity { bus_type: 3, vendor_id: 0x045e, product_id: 0x028e, version: 0x0114, name: name.to_string() }
+    }
+
+    #[test]
+    fn text_round_trips() {
+        let mut pad = Calibration::new();
+        pad.set_axis(0, AxisCalibration { minimum: -32768, center: -120, maximum: 32767 });
+        pad.set_axis(1, AxisCalibration { minimum: -31002, center: 310, maximum: 32767 });
+        let mut store = CalibrationStore::new();
+        store.insert(identity("Microsoft X-Box 360 pad"), pad);
+        store.insert(identity("Second pad"), Calibration::new());
+
+        let text = store.to_string();
+        assert_eq!(
+            text,
+            "[0003:045e:028e:0114] Microsoft X-Box 360 pad\n0 = -32768 -120 32767\n1 = -31002 310 32767\n\
+             [0003:045e:028e:0114] Second pad\n",
+        );
+        assert_eq!(CalibrationStore::parse(&text).unwrap(), store);
+    }
+
+    #[test]
+    fn parse_skips_comments_and_blank_lines() {
+        let store = CalibrationStore::parse("# saved\n\n[0003:045e:028e:0114]  Pad \n  2 = 0 128 255\n").unwrap();
+        let axis = store.get(&identity("Pad")).unwrap().axis(2);
+        assert_eq!(axis, Some(&AxisCalibration { minimum: 0, center: 128, maximum: 255 }));
+    }
+
+    #[test]
+    fn parse_errors_name_the_line() {
+        let error = |text: &str| CalibrationStore::parse(text).unwrap_err().to_string();
+        assert_eq!(error("0 = 1 2 3"), "line 1: axis entry before any device header");
+        assert_eq!(error("[0003:045e:028e] Pad"), "line 1: expected bus:vendor:product:version");
+        assert_eq!(error("[0003:045e:028e:zz] Pad"), "line 1: device ids must be hexadecimal");
+        assert_eq!(error("[0003:045e:028e:0114 Pad"), "line 1: unterminated device header");
+        assert_eq!(error("[0003:045e:028e:0114] Pad\n0 = 1 2"), "line 2: expected `code = min center max`");
+        assert_eq!(error("[0003:045e:028e:0114] Pad\nx = 1 2 3"), "line 2: invalid axis code");
+    }
+}
@@ -0,0 +1,390 @@
+//! Support for the SDL `gamecontrollerdb.txt` mapping format.
+//!
+//! A mapping line looks like this:
+//!
+//! ```text
+//! 030000005e0400008e02000010010000,Xbox 360 Controller,a:b0,b:b1,leftx:a0,dpup:h0.1,platform:Linux,
+//! ```
+//!
+//! The first field is the device GUID, the second its name, and the rest bind an element of the
+//! standard layout to a raw button (`bN`), axis (`aN`) or hat direction (`hN.M`) of the device.
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use super::{GamepadAxis, GamepadButton, GamepadIdentity, Trigger};
+
+/// Mappings for common pads, used when no user mapping matches.
+const BUILTIN_MAPPINGS: &str = "\
+030000005e0400008e02000010010000,Xbox 360 Controller,a:b0,b:b1,back:b6,dpdown:h0.4,dpleft:h0.8,dpright:h0.2,dpup:h0.1,guide:b8,leftshoulder:b4,leftstick:b9,lefttrigger:a2,leftx:a0,lefty:a1,rightshoulder:b5,rightstick:b10,righttrigger:a5,rightx:a3,righty:a4,start:b7,x:b2,y:b3,platform:Linux,
+030000005e040000ea02000001030000,Xbox One Wireless Controller,a:b0,b:b1,back:b6,dpdown:h0.4,dpleft:h0.8,dpright:h0.2,dpup:h0.1,guide:b8,leftshoulder:b4,leftstick:b9,lefttrigger:a2,leftx:a0,lefty:a1,rightshoulder:b5,rightstick:b10,righttrigger:a5,rightx:a3,righty:a4,start:b7,x:b2,y:b3,platform:Linux,
+030000004c050000c405000011810000,PS4 Controller,a:b0,b:b1,back:b8,dpdown:h0.4,dpleft:h0.8,dpright:h0.2,dpup:h0.1,guide:b10,leftshoulder:b4,leftstick:b11,lefttrigger:a2,leftx:a0,lefty:a1,rightshoulder:b5,rightstick:b12,righttrigger:a5,rightx:a3,righty:a4,start:b9,x:b3,y:b2,platform:Linux,
+030000004c050000e60c000011810000,PS5 Controller,a:b0,b:b1,back:b8,dpdown:h0.4,dpleft:h0.8,dpright:h0.2,dpup:h0.1,guide:b10,leftshoulder:b4,leftstick:b11,lefttrigger:a2,leftx:a0,lefty:a1,rightshoulder:b5,rightstick:b12,righttrigger:a5,rightx:a3,righty:a4,start:b9,x:b3,y:b2,platform:Linux,
+";
+
+/// The SDL GUID of a device, built from its bus, vendor, product and version ids.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct GamepadGuid(pub [u8; 16]);
+
+impl GamepadGuid {
+    /// Builds the GUID SDL uses for a device with the given identity.
+    pub fn from_identity(identity: &GamepadIdentity) -> Self {
+        let mut bytes = [0; 16];
+        bytes[0..2].copy_from_slice(&identity.bus_type.to_le_bytes());
+        bytes[4..6].copy_from_slice(&identity.vendor_id.to_le_bytes());
+        bytes[8..10].copy_from_slice(&identity.product_id.to_le_bytes());
+        bytes[12..14].copy_from_slice(&identity.version.to_le_bytes());
+        GamepadGuid(bytes)
+    }
+
+    /// Parses a GUID from its 32 character hexadecimal form.
+    pub fn parse(text: &str) -> Option<Self> {
+        if text.len() != 32 || !text.is_ascii() {
+            return None;
+        }
+        let mut bytes = [0; 16];
+        for (index, byte) in bytes.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&text[index * 2..index * 2 + 2], 16).ok()?;
+        }
+        Some(GamepadGuid(bytes))
+    }
+
+    /// Newer SDL versions store a CRC of the device name in bytes 2 and 3, which this library
+    /// doesn't compute, so those bytes are ignored when matching.
+    fn without_crc(self) -> Self {
+        let mut bytes = self.0;
+        bytes[2] = 0;
+        bytes[3] = 0;
+        GamepadGuid(bytes)
+    }
+
+    fn without_version(self) -> Self {
+        let mut bytes = self.without_crc().0;
+        bytes[12] = 0;
+        bytes[13] = 0;
+        GamepadGuid(bytes)
+    }
+}
+
+impl fmt::Display for GamepadGuid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for byte in self.0 {
+            write!(f, "{:02x}", byte)?;
+        }
+        Ok(())
+    }
+}
+
+/// Which part of a raw axis a binding reads or writes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum AxisRange {
+    Full,
+    Positive,
+    Negative,
+}
+
+/// A raw element of the device, indexed the way SDL enumerates them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum MappingSource {
+    Button(usize),
+    Axis { index: usize, range: AxisRange, inverted: bool },
+    Hat { index: usize, mask: u8 },
+}
+
+/// An element of the standard layout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum MappingTarget {
+    Button(GamepadButton),
+    Axis(GamepadAxis, AxisRange),
+    /// Triggers are analog axes that also report as buttons.
+    Trigger(Trigger),
+}
+
+/// A parsed mapping for one model of gamepad.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GamepadMapping {
+    guid: GamepadGuid,
+    name: String,
+    pub(crate) bindings: Vec<(MappingTarget, MappingSource)>,
+}
+
+impl GamepadMapping {
+    /// Parses a single mapping line.
+    ///
+    /// Returns `Ok(None)` for mappings that target a platform other than Linux.
+    pub fn parse(line: &str) -> io::Result<Option<Self>> {
+        let invalid = |message: String| io::Error::new(io::ErrorKind::InvalidData, message);
+
+        let mut fields = line.trim().split(',');
+        let guid = fields.next().unwrap_or_default();
+        let guid = GamepadGuid::parse(guid).ok_or_else(|| invalid(format!("invalid GUID `{}`", guid)))?;
+        let name = fields.next().ok_or_else(|| invalid("missing device name".to_string()))?.to_string();
+
+        let mut bindings = Vec::new();
+        for field in fields.filter(|field| !field.is_empty()) {
+            let (target, source) = field.split_once(':')
+                .ok_or_else(|| invalid(format!("expected `name:element`, found `{}`", field)))?;
+            if target == "platform" {
+                if source != "Linux" {
+                    return Ok(None);
+                }
+                continue;
+            }
+            let Some(target) = parse_target(target) else {
+                // Unknown targets (crc, hint, touchpad, paddles, ...) are not part of the layout.
+                continue;
+            };
+            let source = parse_source(source)
+                .ok_or_else(|| invalid(format!("invalid element `{}` in `{}`", source, field)))?;
+            bindings.push((target, source));
+        }
+
+        Ok(Some(GamepadMapping { guid, name, bindings }))
+    }
+
+    /// Returns the GUID this mapping applies to.
+    pub fn guid(&self) -> GamepadGuid {
+        self.guid
+    }
+
+    /// Returns the name of the mapping.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// A set of mappings, with user-supplied ones taking precedence over the built-in ones.
+#[derive(Debug, Clone)]
+pub struct MappingDb {
+    user: Vec<GamepadMapping>,
+    builtin: Vec<GamepadMapping>,
+}
+
+impl Default for MappingDb {
+    fn default() -> Self {
+        let builtin = BUILTIN_MAPPINGS.lines()
+            .filter_map(|line| GamepadMapping::parse(line).ok().flatten())
+            .collect();
+        MappingDb { user: Vec::new(), builtin }
+    }
+}
+
+impl MappingDb {
+    /// Creates a database holding only the built-in mappings.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds mappings in `gamecontrollerdb.txt` format, one per line, and returns how many
+    /// were added. Later mappings override earlier ones for the same GUID.
+    ///
+    /// Comment lines starting with `#` are skipped. If any line is invalid, nothing is added
+    /// and the error names the offending line.
+    pub fn add_mappings(&mut self, text: &str) -> io::Result<usize> {
+        let mut parsed = Vec::new();
+        for (index, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let mapping = GamepadMapping::parse(line).map_err(|err| {
+                io::Error::new(io::ErrorKind::InvalidData, format!("line {}: {}", index + 1, err))
+            })?;
+            parsed.extend(mapping);
+        }
+
+        let added = parsed.len();
+        for mapping in parsed {
+            self.user.retain(|existing| existing.guid != mapping.guid);
+            self.user.push(mapping);
+        }
+        Ok(added)
+    }
+
+    /// Adds every mapping from a `gamecontrollerdb.txt` file.
+    pub fn load(&mut self, path: impl AsRef<Path>) -> io::Result<usize> {
+        self.add_mappings(&fs::read_to_string(path)?)
+    }
+
+    /// Finds the mapping for a device.
+    ///
+    /// An exact GUID match is preferred, followed by a match that ignores the product version.
+    pub fn find(&self, identity: &GamepadIdentity) -> Option<&GamepadMapping> {
+        let guid = GamepadGuid::from_identity(identity);
+        let exact = |mapping: &&GamepadMapping| mapping.guid.without_crc() == guid;
+        let any_version = |mapping: &&GamepadMapping| mapping.guid.without_version() == guid.without_version();
+
+        self.user.iter().rev().find(exact)
+            .or_else(|| self.builtin.iter().find(exact))
+            .or_else(|| self.user.iter().rev().find(any_version))
+            .or_else(|| self.builtin.iter().find(any_version))
+    }
+}
+
+fn parse_target(name: &str) -> Option<MappingTarget> {
+    let (range, name) = match name.split_at_checked(1) {
+        Some(("+", rest)) => (AxisRange::Positive, rest),
+        Some(("-", rest)) => (AxisRange::Negative, rest),
+        _ => (AxisRange::Full, name),
+    };
+    let button = |button| (range == AxisRange::Full).then_some(MappingTarget::Button(button));
+    match name {
+        "a" => button(GamepadButton::South),
+        "b" => button(GamepadButton::East),
+        "x" => button(GamepadButton::West),
+        "y" => button(GamepadButton::North),
+        "back" => button(GamepadButton::Select),
+        "guide" => button(GamepadButton::Mode),
+        "start" => button(GamepadButton::Start),
+        "leftstick" => button(GamepadButton::LeftThumb),
+        "rightstick" => button(GamepadButton::RightThumb),
+        "leftshoulder" => button(GamepadButton::LeftTrigger),
+        "rightshoulder" => button(GamepadButton::RightTrigger),
+        "dpup" => button(GamepadButton::DPadUp),
+        "dpdown" => button(GamepadButton::DPadDown),
+        "dpleft" => button(GamepadButton::DPadLeft),
+        "dpright" => button(GamepadButton::DPadRight),
+        "lefttrigger" => Some(MappingTarget::Trigger(Trigger::Left)),
+        "righttrigger" => Some(MappingTarget::Trigger(Trigger::Right)),
+        "leftx" => Some(MappingTarget::Axis(GamepadAxis::LeftStickX, range)),
+        "lefty" => Some(MappingTarget::Axis(GamepadAxis::LeftStickY, range)),
+        "rightx" => Some(MappingTarget::Axis(GamepadAxis::RightStickX, range)),
+        "righty" => Some(MappingTarget::Axis(GamepadAxis::RightStickY, range)),
+        _ => None,
+    }
+}
+
+fn parse_source(element: &str) -> Option<MappingSource> {
+    let (range, element) = match element.split_at_checked(1) {
+        Some(("+", rest)) => (AxisRange::Positive, rest),
+        Some(("-", rest)) => (AxisRange::Negative, rest),
+        _ => (AxisRange::Full, element),
+    };
+    let (element, inverted) = match element.strip_suffix('~') {
+        Some(element) => (element, true),
+        None => (element, false),
+    };
+
+    if let Some(index) = element.strip_prefix('b') {
+        return Some(MappingSource::Button(index.parse().ok()?));
+    }
+    if let Some(index) = element.strip_prefix('a') {
+        return Some(MappingSource::Axis { index: index.parse().ok()?, range, inverted });
+    }
+    if let Some(hat) = element.strip_prefix('h') {
+        let (index, mask) = hat.split_once('.')?;
+        return Some(MappingSource::Hat { index: index.parse().ok()?, mask: mask.parse().ok()? });
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GUID: &str = "030000005e0400008e02000010010000";
+
+    fn identity(version: u16) -> GamepadIdentity {
+        GamepadIdentity { bus_type: 3, vendor_id: 0x045e, product_id: 0x028e, version, name: String::new() }
+    }
+
+    fn source(mapping: &GamepadMapping, target: MappingTarget) -> Option<MappingSource> {
+        mapping.bindings.iter().find(|(bound, _)| *bound == target).map(|(_, source)| *source)
+    }
+
+    #[test]
+    fn guid_round_trips_through_identity_and_text() {
+        let guid = GamepadGuid::parse(GUID).unwrap();
+        assert_eq!(guid, GamepadGuid::from_identity(&identity(0x0110)));
+        assert_eq!(guid.to_string(), GUID);
+        assert_eq!(GamepadGuid::parse("030000005e04"), None);
+        assert_eq!(GamepadGuid::parse(&GUID.replace('5', "g")), None);
+    }
+
+    #[test]
+    fn parses_buttons_axes_and_hats() {
+        let line = format!("{},Pad,a:b0,leftx:a0,dpup:h0.1,dpleft:h0.8,lefttrigger:a2,platform:Linux,", GUID);
+        let mapping = GamepadMapping::parse(&line).unwrap().unwrap();
+        assert_eq!(mapping.name(), "Pad");
+        assert_eq!(source(&mapping, MappingTarget::Button(GamepadButton::South)), Some(MappingSource::Button(0)));
+        assert_eq!(
+            source(&mapping, MappingTarget::Axis(GamepadAxis::LeftStickX, AxisRange::Full)),
+            Some(MappingSource::Axis { index: 0, range: AxisRange::Full, inverted: false }),
+        );
+        assert_eq!(source(&mapping, MappingTarget::Button(GamepadButton::DPadUp)), Some(MappingSource::Hat { index: 0, mask: 1 }));
+        assert_eq!(source(&mapping, MappingTarget::Button(GamepadButton::DPadLeft)), Some(MappingSource::Hat { index: 0, mask: 8 }));
+        assert_eq!(
+            source(&mapping, MappingTarget::Trigger(Trigger::Left)),
+            Some(MappingSource::Axis { index: 2, range: AxisRange::Full, inverted: false }),
+        );
+    }
+
+    #[test]
+    fn parses_half_and_inverted_axes() {
+        let line = format!("{},Pad,+leftx:+a3,-leftx:-a3~,righty:a4~,dpdown:+a7,", GUID);
+        let mapping = GamepadMapping::parse(&line).unwrap().unwrap();
+        assert_eq!(
+            source(&mapping, MappingTarget::Axis(GamepadAxis::LeftStickX, AxisRange::Positive)),
+            Some(MappingSource::Axis { index: 3, range: AxisRange::Positive, inverted: false }),
+        );
+        assert_eq!(
+            source(&mapping, MappingTarget::Axis(GamepadAxis::LeftStickX, AxisRange::Negative)),
+            Some(MappingSource::Axis { index: 3, range: AxisRange::Negative, inverted: true }),
+        );
+        assert_eq!(
+            source(&mapping, MappingTarget::Axis(GamepadAxis::RightStickY, AxisRange::Full)),
+            Some(MappingSource::Axis { index: 4, range: AxisRange::Full, inverted: true }),
+        );
+        assert_eq!(
+            source(&mapping, MappingTarget::Button(GamepadButton::DPadDown)),
+            Some(MappingSource::Axis { index: 7, range: AxisRange::Positive, inverted: false }),
+        );
+    }
+
+    #[test]
+    fn skips_unknown_targets_and_other_platforms() {
+        let line = format!("{},Pad,a:b0,touchpad:b13,crc:1234,", GUID);
+        assert_eq!(GamepadMapping::parse(&line).unwrap().unwrap().bindings.len(), 1);
+        let line = format!("{},Pad,a:b0,platform:Windows,", GUID);
+        assert_eq!(GamepadMapping::parse(&line).unwrap(), None);
+    }
+
+    #[test]
+    fn rejects_invalid_lines() {
+        assert!(GamepadMapping::parse("nope,Pad,a:b0").is_err());
+        assert!(GamepadMapping::parse(GUID).is_err());
+        assert!(GamepadMapping::parse(&format!("{},Pad,a", GUID)).is_err());
+        assert!(GamepadMapping::parse(&format!("{},Pad,a:c0", GUID)).is_err());
+        assert!(GamepadMapping::parse(&format!("{},Pad,dpup:h0", GUID)).is_err());
+    }
+
+    #[test]
+    fn user_mappings_override_builtin_ones() {
+        let mut db = MappingDb::new();
+        assert_eq!(db.find(&identity(0x0110)).unwrap().name(), "Xbox 360 Controller");
+
+        let added = db.add_mappings(&format!("# comment\n{},First,a:b1,\n{},Second,a:b2,\n", GUID, GUID)).unwrap();
+        assert_eq!(added, 2);
+        let mapping = db.find(&identity(0x0110)).unwrap();
+        assert_eq!(mapping.name(), "Second");
+        assert_eq!(source(mapping, MappingTarget::Button(GamepadButton::South)), Some(MappingSource::Button(2)));
+    }
+
+    #[test]
+    fn matches_other_versions_and_ignores_the_name_crc() {
+        let mut db = MappingDb::new();
+        db.add_mappings("03001234a0000000b000000000010000,Versioned,a:b0,\n").unwrap();
+        let device = GamepadIdentity { bus_type: 3, vendor_id: 0xa0, product_id: 0xb0, version: 0x0200, name: String::new() };
+        assert_eq!(db.find(&device).unwrap().name(), "Versioned");
+        assert_eq!(db.find(&identity(0x0999)).unwrap().name(), "Xbox 360 Controller");
+    }
+
+    #[test]
+    fn invalid_lines_add_nothing() {
+        let mut db = MappingDb::new();
+        let err = db.add_mappings(&format!("{},Pad,a:b0,\nbroken\n", GUID)).unwrap_err();
+        assert!(err.to_string().starts_with("line 2: "));
+        assert_eq!(db.find(&identity(0x0110)).unwrap().name(), "Xbox 360 Controller");
+    }
+}
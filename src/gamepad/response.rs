@@ -0,0 +1,359 @@
+//! Dead zones, saturation and response curves applied to normalised axis values.
+
+use std::collections::HashMap;
+use super::{GamepadAxis, GamepadButton};
+
+/// Shapes how a normalised axis value maps onto the reported value.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum ResponseCurve {
+    /// The value is reported unchanged.
+    #[default]
+    Linear,
+    /// The magnitude is raised to the given power, keeping the sign. Exponents above `1.0`
+    /// give finer control near the center.
+    Exponential(f32),
+}
+
+impl ResponseCurve {
+    #[cfg(any(target_os = "linux", test))]
+    fn apply(self, value: f32) -> f32 {
+        match self {
+            ResponseCurve::Linear => value,
+            ResponseCurve::Exponential(exponent) => value.abs().powf(exponent) * value.signum(),
+        }
+    }
+}
+
+/// Processing applied to a single axis.
+///
+/// Values inside `dead_zone` read as zero, values beyond `saturation` read as full
+/// deflection, and the range in between is rescaled to `0.0..=1.0` before the curve is applied.
+/// Analog triggers rest at `-1.0`, so on them the fractions are of how far they are pulled.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AxisSettings {
+    /// Inner dead zone, as a fraction of full deflection
+    pub dead_zone: f32,
+    /// Deflection at which the axis reports its maximum, as a fraction of full deflection
+    pub saturation: f32,
+    /// Response curve applied after the dead zone and saturation
+    pub curve: ResponseCurve,
+}
+
+impl Default for AxisSettings {
+    fn default() -> Self {
+        AxisSettings {
+            dead_zone: 0.0,
+            saturation: 1.0,
+            curve: ResponseCurve::Linear,
+        }
+    }
+}
+
+impl AxisSettings {
+    #[cfg(any(target_os = "linux", test))]
+    fn apply(&self, value: f32) -> f32 {
+        let magnitude = rescale(value.abs(), self.dead_zone, self.saturation);
+        self.curve.apply(magnitude * value.signum())
+    }
+}
+
+/// An analog stick, made up of an X and a Y axis.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Stick {
+    Left,
+    Right,
+}
+
+impl Stick {
+    /// Returns the X and Y axes that make up this stick.
+    pub fn axes(self) -> (GamepadAxis, GamepadAxis) {
+        match self {
+            Stick::Left => (GamepadAxis::LeftStickX, GamepadAxis::LeftStickY),
+            Stick::Right => (GamepadAxis::RightStickX, GamepadAxis::RightStickY),
+        }
+    }
+}
+
+/// An analog trigger, which is reported both as an axis and as a button.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Trigger {
+    Left,
+    Right,
+}
+
+impl Trigger {
+    /// Returns the axis that carries this trigger's analog value.
+    pub fn axis(self) -> GamepadAxis {
+        match self {
+            Trigger::Left => GamepadAxis::LeftZ,
+            Trigger::Right => GamepadAxis::RightZ,
+        }
+    }
+
+    /// Returns the button this trigger is reported as.
+    pub fn button(self) -> GamepadButton {
+        match self {
+            Trigger::Left => GamepadButton::LeftTrigger2,
+            Trigger::Right => GamepadButton::RightTrigger2,
+        }
+    }
+}
+
+/// Thresholds at which an analog trigger is reported as a pressed button.
+///
+/// The button goes down once the trigger reaches `press_threshold` and stays down until it
+/// falls back to `release_threshold`, so a trigger resting near a single threshold doesn't
+/// flicker between pressed and released.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TriggerSettings {
+    /// Trigger value in `0.0..=1.0` at which the button is pressed
+    pub press_threshold: f32,
+    /// Trigger value in `0.0..=1.0` at which the button is released
+    pub release_threshold: f32,
+}
+
+impl Default for TriggerSettings {
+    fn default() -> Self {
+        TriggerSettings {
+            press_threshold: 0.5,
+            release_threshold: 0.3,
+        }
+    }
+}
+
+impl TriggerSettings {
+    #[cfg(any(target_os = "linux", test))]
+    pub(crate) fn update(&self, pressed: bool, value: f32) -> bool {
+        if pressed {
+            value > self.release_threshold
+        } else {
+            value >= self.press_threshold
+        }
+    }
+}
+
+/// Processing applied to both axes of a stick together.
+///
+/// Unlike per-axis dead zones, a radial dead zone is measured on the distance from the center,
+/// so it doesn't snap diagonal movement onto the cardinal directions.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StickSettings {
+    /// Radial dead zone, as a fraction of full deflection
+    pub dead_zone: f32,
+    /// Distance from the center at which the stick reports full deflection
+    pub saturation: f32,
+    /// Response curve applied to the distance from the center
+    pub curve: ResponseCurve,
+}
+
+impl Default for StickSettings {
+    fn default() -> Self {
+        StickSettings {
+            dead_zone: 0.0,
+            saturation: 1.0,
+            curve: ResponseCurve::Linear,
+        }
+    }
+}
+
+impl StickSettings {
+    #[cfg(any(target_os = "linux", test))]
+    fn apply(&self, x: f32, y: f32) -> (f32, f32) {
+        let distance = x.hypot(y);
+        if distance == 0.0 {
+            return (0.0, 0.0);
+        }
+        let scaled = self.curve.apply(rescale(distance, self.dead_zone, self.saturation));
+        let factor = scaled / distance;
+        ((x * factor).clamp(-1.0, 1.0), (y * factor).clamp(-1.0, 1.0))
+    }
+}
+
+/// Axis processing for a gamepad.
+///
+/// Per-axis settings are applied first, followed by the radial settings of each stick.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GamepadSettings {
+    axes: HashMap<GamepadAxis, AxisSettings>,
+    sticks: HashMap<Stick, StickSettings>,
+    triggers: HashMap<Trigger, TriggerSettings>,
+}
+
+impl GamepadSettings {
+    /// Creates settings that leave every axis unchanged.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the processing for a single axis.
+    pub fn with_axis(mut self, axis: GamepadAxis, settings: AxisSettings) -> Self {
+        self.axes.insert(axis, settings);
+        self
+    }
+
+    /// Sets the radial processing for a stick.
+    pub fn with_stick(mut self, stick: Stick, settings: StickSettings) -> Self {
+        self.sticks.insert(stick, settings);
+        self
+    }
+
+    /// Sets the press and release thresholds of a trigger.
+    pub fn with_trigger(mut self, trigger: Trigger, settings: TriggerSettings) -> Self {
+        self.triggers.insert(trigger, settings);
+        self
+    }
+
+    /// Returns the processing for an axis, if any was set.
+    pub fn axis(&self, axis: GamepadAxis) -> Option<&AxisSettings> {
+        self.axes.get(&axis)
+    }
+
+    /// Returns the radial processing for a stick, if any was set.
+    pub fn stick(&self, stick: Stick) -> Option<&StickSettings> {
+        self.sticks.get(&stick)
+    }
+
+    /// Returns the thresholds of a trigger, falling back to the defaults.
+    pub fn trigger(&self, trigger: Trigger) -> TriggerSettings {
+        self.triggers.get(&trigger).copied().unwrap_or_default()
+    }
+
+    /// Processes the axes of a gamepad. The `triggers` rest at `-1.0`, so their settings
+    /// apply to how far they are pulled, from `0.0` to `1.0`.
+    #[cfg(any(target_os = "linux", test))]
+    pub(crate) fn apply(&self, axes: &mut [(GamepadAxis, f32)], triggers: &[GamepadAxis]) {
+        for (axis, value) in axes.iter_mut() {
+            if let Some(settings) = self.axes.get(axis) {
+                *value = if triggers.contains(axis) {
+                    settings.apply((*value + 1.0) / 2.0) * 2.0 - 1.0
+                } else {
+                    settings.apply(*value)
+                };
+            }
+        }
+
+        for (stick, settings) in &self.sticks {
+            let (x_axis, y_axis) = stick.axes();
+            let x_index = axes.iter().position(|(axis, _)| *axis == x_axis);
+            let y_index = axes.iter().position(|(axis, _)| *axis == y_axis);
+            if let (Some(x_index), Some(y_index)) = (x_index, y_index) {
+                let (x, y) = settings.apply(axes[x_index].1, axes[y_index].1);
+                axes[x_index].1 = x;
+                axes[y_index].1 = y;
+            }
+        }
+    }
+}
+
+/// Maps `dead_zone..=saturation` onto `0.0..=1.0`, clamping on either side.
+#[cfg(any(target_os = "linux", test))]
+fn rescale(magnitude: f32, dead_zone: f32, saturation: f32) -> f32 {
+    if magnitude <= dead_zone {
+        return 0.0;
+    }
+    if saturation <= dead_zone {
+        return 1.0;
+    }
+    ((magnitude - dead_zone) / (saturation - dead_zone)).min(1.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(value: f32, expected: f32) {
+        assert!((value - expected).abs() < 1e-5, "{} != {}", value, expected);
+    }
+
+    #[test]
+    fn rescale_clamps_outside_the_live_range() {
+        assert_eq!(rescale(0.1, 0.2, 0.8), 0.0);
+        assert_eq!(rescale(0.2, 0.2, 0.8), 0.0);
+        assert_close(rescale(0.5, 0.2, 0.8), 0.5);
+        assert_eq!(rescale(0.9, 0.2, 0.8), 1.0);
+        // Saturation at or inside the dead zone turns the axis into a switch.
+        assert_eq!(rescale(0.5, 0.4, 0.3), 1.0);
+        assert_eq!(rescale(0.3, 0.4, 0.3), 0.0);
+    }
+
+    #[test]
+    fn curves_keep_the_sign() {
+        assert_eq!(ResponseCurve::Linear.apply(-0.5), -0.5);
+        assert_close(ResponseCurve::Exponential(2.0).apply(0.5), 0.25);
+        assert_close(ResponseCurve::Exponential(2.0).apply(-0.5), -0.25);
+        assert_close(ResponseCurve::Exponential(0.5).apply(0.25), 0.5);
+        assert_eq!(ResponseCurve::Exponential(3.0).apply(1.0), 1.0);
+    }
+
+    #[test]
+    fn axis_settings_apply_the_dead_zone_before_the_curve() {
+        let settings = AxisSettings { dead_zone: 0.2, saturation: 0.8, curve: ResponseCurve::Exponential(2.0) };
+        assert_eq!(settings.apply(0.1), 0.0);
+        assert_close(settings.apply(0.5), 0.25);
+        assert_close(settings.apply(-0.5), -0.25);
+        assert_eq!(settings.apply(-0.9), -1.0);
+        assert_eq!(AxisSettings::default().apply(-0.3), -0.3);
+    }
+
+    #[test]
+    fn radial_dead_zones_keep_the_direction() {
+        let settings = StickSettings { dead_zone: 0.2, ..Default::default() };
+        assert_eq!(settings.apply(0.1, -0.1), (0.0, 0.0));
+        assert_eq!(settings.apply(0.0, 0.0), (0.0, 0.0));
+        // A diagonal whose axes are each inside the dead zone still moves.
+        let (x, y) = settings.apply(0.18, 0.18);
+        assert!(x > 0.0 && y > 0.0);
+        assert_close(x, y);
+        let (x, y) = settings.apply(0.6, 0.0);
+        assert_close(x, 0.5);
+        assert_eq!(y, 0.0);
+
+        let settings = StickSettings { saturation: 0.5, ..Default::default() };
+        let (x, y) = settings.apply(0.3, 0.4);
+        assert_close(x.hypot(y), 1.0);
+        assert_close(x / y, 0.75);
+    }
+
+    #[test]
+    fn settings_apply_per_axis_then_per_stick() {
+        let settings = GamepadSettings::new()
+            .with_axis(GamepadAxis::LeftStickX, AxisSettings { dead_zone: 0.5, ..Default::default() })
+            .with_stick(Stick::Left, StickSettings { saturation: 0.5, ..Default::default() });
+        let mut axes = [
+            (GamepadAxis::LeftStickX, 0.75), (GamepadAxis::LeftStickY, 0.0), (GamepadAxis::RightStickX, 0.3),
+        ];
+        settings.apply(&mut axes, &[]);
+        // X is halved by its own dead zone, then doubled by the stick's saturation.
+        assert_close(axes[0].1, 1.0);
+        assert_eq!(axes[1].1, 0.0);
+        assert_eq!(axes[2].1, 0.3);
+    }
+
+    #[test]
+    fn trigger_dead_zones_are_measured_from_rest() {
+        let settings = GamepadSettings::new()
+            .with_axis(GamepadAxis::LeftZ, AxisSettings { dead_zone: 0.1, ..Default::default() });
+        // Released, slightly pulled, half pulled and fully pulled.
+        let mut axes = [-1.0, -0.9, 0.0, 1.0].map(|value| (GamepadAxis::LeftZ, value));
+        settings.apply(&mut axes, &[GamepadAxis::LeftZ]);
+        assert_eq!(axes[0].1, -1.0);
+        assert_eq!(axes[1].1, -1.0);
+        assert_close(axes[2].1, -1.0 + 2.0 * 0.4 / 0.9);
+        assert_eq!(axes[3].1, 1.0);
+
+        // The same axis on a joystick is centered.
+        let mut axes = [(GamepadAxis::LeftZ, 0.05), (GamepadAxis::LeftZ, -0.9)];
+        settings.apply(&mut axes, &[]);
+        assert_eq!(axes[0].1, 0.0);
+        assert_close(axes[1].1, -0.8 / 0.9);
+    }
+
+    #[test]
+    fn trigger_thresholds_have_hysteresis() {
+        let settings = TriggerSettings::default();
+        assert!(!settings.update(false, 0.4));
+        assert!(settings.update(false, 0.5));
+        assert!(settings.update(true, 0.4));
+        assert!(!settings.update(true, 0.3));
+    }
+}
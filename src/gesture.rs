@@ -0,0 +1,340 @@
+//! Touchpad gesture recognition from multitouch contacts.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use crate::touch::{TouchAxisRange, TouchContact};
+
+/// The main direction of a swipe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SwipeDirection {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+/// A gesture recognized from the contacts on a touch surface.
+///
+/// Distances are in millimetres when the device reports its resolution, and in hundredths of
+/// the surface's longer side otherwise. Swipes and pinches are reported as a begin event, a
+/// series of updates and an end event; taps are reported once the fingers lift.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Gesture {
+    /// Several fingers started moving together.
+    SwipeBegin { fingers: usize },
+    /// The fingers of a swipe moved; `dx` and `dy` are the total movement since it began.
+    SwipeUpdate { fingers: usize, dx: f32, dy: f32 },
+    /// The fingers of a swipe lifted, or another finger touched down.
+    SwipeEnd { fingers: usize, dx: f32, dy: f32 },
+    /// Several fingers started moving apart, together or around each other.
+    PinchBegin { fingers: usize },
+    /// The fingers of a pinch moved. `scale` is the spread relative to when the pinch began
+    /// and `rotation` the clockwise rotation since then, in degrees.
+    PinchUpdate { fingers: usize, scale: f32, rotation: f32 },
+    /// The fingers of a pinch lifted, or another finger touched down.
+    PinchEnd { fingers: usize, scale: f32, rotation: f32 },
+    /// Fingers briefly touched the surface without moving. One finger is a primary click, two
+    /// a secondary click and three a middle click.
+    Tap { fingers: usize },
+}
+
+impl Gesture {
+    /// Returns the main direction of a swipe, or `None` for other gestures.
+    pub fn swipe_direction(&self) -> Option<SwipeDirection> {
+        match *self {
+            Gesture::SwipeUpdate { dx, dy, .. } | Gesture::SwipeEnd { dx, dy, .. } => {
+                Some(if dx.abs() >= dy.abs() {
+                    if dx < 0.0 { SwipeDirection::Left } else { SwipeDirection::Right }
+                } else if dy < 0.0 {
+                    SwipeDirection::Up
+                } else {
+                    SwipeDirection::Down
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Thresholds used to tell gestures apart.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GestureConfig {
+    /// Longest time fingers can stay down and still count as a tap
+    pub tap_timeout: Duration,
+    /// Furthest a finger can move and still count as a tap
+    pub tap_max_movement: f32,
+    /// Distance the fingers must move together before a swipe begins
+    pub swipe_threshold: f32,
+    /// Change in spread, as a fraction, before a pinch begins
+    pub pinch_threshold: f32,
+    /// Rotation, in degrees, before a pinch begins
+    pub rotation_threshold: f32,
+}
+
+impl Default for GestureConfig {
+    fn default() -> Self {
+        GestureConfig {
+            tap_timeout: Duration::from_millis(180),
+            tap_max_movement: 3.0,
+            swipe_threshold: 5.0,
+            pinch_threshold: 0.1,
+            rotation_threshold: 10.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Active {
+    None,
+    Swipe,
+    Pinch,
+}
+
+/// Turns a stream of contact frames from one device into gestures.
+#[derive(Debug, Clone)]
+pub struct GestureRecognizer {
+    config: GestureConfig,
+    /// Millimetres per device unit on each axis
+    scale: (f32, f32),
+    /// When the first finger of the current touch sequence went down
+    touch_started: Option<Instant>,
+    max_fingers: usize,
+    /// Whether any finger moved too far for the sequence to be a tap
+    moved: bool,
+    first_positions: HashMap<i32, (f32, f32)>,
+    /// Positions when the current finger count was reached, used as the gesture baseline
+    baseline: HashMap<i32, (f32, f32)>,
+    active: Active,
+    last: Option<Gesture>,
+}
+
+impl GestureRecognizer {
+    /// Creates a recognizer for a device with the given axis ranges.
+    pub fn new(config: GestureConfig, x_range: TouchAxisRange, y_range: TouchAxisRange) -> Self {
+        let axis_scale = |range: TouchAxisRange, fallback: f32| {
+            if range.resolution > 0 { 1.0 / range.resolution as f32 } else { fallback }
+        };
+        let longest = (x_range.maximum - x_range.minimum).max(y_range.maximum - y_range.minimum).max(1);
+        let fallback = 100.0 / longest as f32;
+
+        GestureRecognizer {
+            config,
+            scale: (axis_scale(x_range, fallback), axis_scale(y_range, fallback)),
+            touch_started: None,
+            max_fingers: 0,
+            moved: false,
+            first_positions: HashMap::new(),
+            baseline: HashMap::new(),
+            active: Active::None,
+            last: None,
+        }
+    }
+
+    /// Replaces the thresholds used for gestures that haven't begun yet.
+    pub fn set_config(&mut self, config: GestureConfig) {
+        self.config = config;
+    }
+
+    /// Processes a complete frame of contacts and returns the gestures it produced.
+    pub fn update(&mut self, contacts: &[TouchContact], now: Instant) -> Vec<Gesture> {
+        let mut gestures = Vec::new();
+        let positions: HashMap<i32, (f32, f32)> = contacts.iter()
+            .map(|contact| (contact.id, (contact.x as f32 * self.scale.0, contact.y as f32 * self.scale.1)))
+            .collect();
+
+        if positions.is_empty() {
+            if let Some(started) = self.touch_started.take() {
+                self.end_gesture(&mut gestures);
+                if !self.moved && now.duration_since(started) <= self.config.tap_timeout && self.max_fingers <= 3 {
+                    gestures.push(Gesture::Tap { fingers: self.max_fingers });
+                }
+            }
+            self.max_fingers = 0;
+            self.moved = false;
+            self.first_positions.clear();
+            self.baseline.clear();
+            return gestures;
+        }
+
+        self.touch_started.get_or_insert(now);
+        self.max_fingers = self.max_fingers.max(positions.len());
+        for (id, position) in &positions {
+            let first = *self.first_positions.entry(*id).or_insert(*position);
+            if distance(first, *position) > self.config.tap_max_movement {
+                self.moved = true;
+            }
+        }
+
+        let same_fingers = positions.len() == self.baseline.len()
+            && positions.keys().all(|id| self.baseline.contains_key(id));
+        if !same_fingers {
+            self.end_gesture(&mut gestures);
+            self.baseline = positions;
+            return gestures;
+        }
+        if positions.len() < 2 {
+            return gestures;
+        }
+
+        let fingers = positions.len();
+        let (dx, dy) = sub(centroid(&positions), centroid(&self.baseline));
+        let scale = spread(&positions) / spread(&self.baseline).max(f32::EPSILON);
+        let rotation = rotation(&self.baseline, &positions);
+
+        if self.active == Active::None {
+            if (scale - 1.0).abs() >= self.config.pinch_threshold
+                || rotation.abs() >= self.config.rotation_threshold
+            {
+                self.active = Active::Pinch;
+                gestures.push(Gesture::PinchBegin { fingers });
+            } else if dx.hypot(dy) >= self.config.swipe_threshold {
+                self.active = Active::Swipe;
+                gestures.push(Gesture::SwipeBegin { fingers });
+            }
+        }
+
+        let update = match self.active {
+            Active::None => None,
+            Active::Swipe => Some(Gesture::SwipeUpdate { fingers, dx, dy }),
+            Active::Pinch => Some(Gesture::PinchUpdate { fingers, scale, rotation }),
+        };
+        if let Some(update) = update
+            && self.last != Some(update)
+        {
+            gestures.push(update);
+            self.last = Some(update);
+        }
+        gestures
+    }
+
+    fn end_gesture(&mut self, gestures: &mut Vec<Gesture>) {
+        let end = match self.last.take() {
+            Some(Gesture::SwipeUpdate { fingers, dx, dy }) => Some(Gesture::SwipeEnd { fingers, dx, dy }),
+            Some(Gesture::PinchUpdate { fingers, scale, rotation }) => Some(Gesture::PinchEnd { fingers, scale, rotation }),
+            _ => None,
+        };
+        gestures.extend(end);
+        self.active = Active::None;
+    }
+}
+
+fn sub(a: (f32, f32), b: (f32, f32)) -> (f32, f32) {
+    (a.0 - b.0, a.1 - b.1)
+}
+
+fn distance(a: (f32, f32), b: (f32, f32)) -> f32 {
+    let (dx, dy) = sub(a, b);
+    dx.hypot(dy)
+}
+
+fn centroid(positions: &HashMap<i32, (f32, f32)>) -> (f32, f32) {
+    let count = positions.len().max(1) as f32;
+    let (x, y) = positions.values().fold((0.0, 0.0), |acc, p| (acc.0 + p.0, acc.1 + p.1));
+    (x / count, y / count)
+}
+
+/// Average distance of the fingers from their centroid.
+fn spread(positions: &HashMap<i32, (f32, f32)>) -> f32 {
+    let center = centroid(positions);
+    positions.values().map(|p| distance(*p, center)).sum::<f32>() / positions.len().max(1) as f32
+}
+
+/// Average clockwise rotation of the fingers around their centroid, in degrees.
+fn rotation(from: &HashMap<i32, (f32, f32)>, to: &HashMap<i32, (f32, f32)>) -> f32 {
+    let (from_center, to_center) = (centroid(from), centroid(to));
+    let mut total = 0.0;
+    for (id, start) in from {
+        let Some(end) = to.get(id) else {
+            continue;
+        };
+        let (sx, sy) = sub(*start, from_center);
+        let (ex, ey) = sub(*end, to_center);
+        let mut delta = ey.atan2(ex) - sy.atan2(sx);
+        if delta > std::f32::consts::PI {
+            delta -= std::f32::consts::TAU;
+        } else if delta < -std::f32::consts::PI {
+            delta += std::f32::consts::TAU;
+        }
+        total += delta;
+    }
+    // Device y grows downwards, so a positive angle is a clockwise rotation.
+    (total / from.len().max(1) as f32).to_degrees()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RANGE: TouchAxisRange = TouchAxisRange { minimum: 0, maximum: 100, resolution: 1 };
+
+    fn contacts(positions: &[(i32, i32)]) -> Vec<TouchContact> {
+        positions.iter().enumerate()
+            .map(|(id, &(x, y))| TouchContact { id: id as i32, x, y, pressure: None, touch_major: None })
+            .collect()
+    }
+
+    fn after(start: Instant, millis: u64) -> Instant {
+        start + Duration::from_millis(millis)
+    }
+
+    #[test]
+    fn taps_count_fingers_within_the_timeout() {
+        let start = Instant::now();
+        let mut recognizer = GestureRecognizer::new(GestureConfig::default(), RANGE, RANGE);
+        assert!(recognizer.update(&contacts(&[(10, 10)]), start).is_empty());
+        assert!(recognizer.update(&contacts(&[(10, 10), (30, 10)]), after(start, 20)).is_empty());
+        assert_eq!(recognizer.update(&[], after(start, 180)), [Gesture::Tap { fingers: 2 }]);
+
+        recognizer.update(&contacts(&[(10, 10)]), after(start, 1000));
+        assert!(recognizer.update(&[], after(start, 1181)).is_empty());
+    }
+
+    #[test]
+    fn moving_fingers_are_not_taps() {
+        let start = Instant::now();
+        let mut recognizer = GestureRecognizer::new(GestureConfig::default(), RANGE, RANGE);
+        recognizer.update(&contacts(&[(10, 10)]), start);
+        recognizer.update(&contacts(&[(14, 10)]), after(start, 10));
+        assert!(recognizer.update(&[], after(start, 20)).is_empty());
+    }
+
+    #[test]
+    fn fingers_moving_together_swipe() {
+        let start = Instant::now();
+        let mut recognizer = GestureRecognizer::new(GestureConfig::default(), RANGE, RANGE);
+        recognizer.update(&contacts(&[(10, 10), (30, 10)]), start);
+        assert!(recognizer.update(&contacts(&[(12, 10), (32, 10)]), after(start, 10)).is_empty());
+        let update = Gesture::SwipeUpdate { fingers: 2, dx: 10.0, dy: 0.0 };
+        assert_eq!(
+            recognizer.update(&contacts(&[(20, 10), (40, 10)]), after(start, 20)),
+            [Gesture::SwipeBegin { fingers: 2 }, update],
+        );
+        assert_eq!(update.swipe_direction(), Some(SwipeDirection::Right));
+        assert_eq!(recognizer.update(&[], after(start, 30)), [Gesture::SwipeEnd { fingers: 2, dx: 10.0, dy: 0.0 }]);
+    }
+
+    #[test]
+    fn fingers_spreading_pinch_until_another_finger_lands() {
+        let start = Instant::now();
+        let mut recognizer = GestureRecognizer::new(GestureConfig::default(), RANGE, RANGE);
+        recognizer.update(&contacts(&[(40, 50), (60, 50)]), start);
+        assert_eq!(
+            recognizer.update(&contacts(&[(30, 50), (70, 50)]), after(start, 10)),
+            [Gesture::PinchBegin { fingers: 2 }, Gesture::PinchUpdate { fingers: 2, scale: 2.0, rotation: 0.0 }],
+        );
+        assert_eq!(
+            recognizer.update(&contacts(&[(30, 50), (70, 50), (50, 80)]), after(start, 20)),
+            [Gesture::PinchEnd { fingers: 2, scale: 2.0, rotation: 0.0 }],
+        );
+    }
+
+    #[test]
+    fn distances_fall_back_to_hundredths_of_the_surface() {
+        let start = Instant::now();
+        let range = TouchAxisRange { minimum: 0, maximum: 1000, resolution: 0 };
+        let mut recognizer = GestureRecognizer::new(GestureConfig::default(), range, range);
+        recognizer.update(&contacts(&[(100, 100), (300, 100)]), start);
+        assert!(recognizer.update(&contacts(&[(140, 100), (340, 100)]), after(start, 10)).is_empty());
+        assert_eq!(recognizer.update(&contacts(&[(150, 100), (350, 100)]), after(start, 20))[0], Gesture::SwipeBegin { fingers: 2 });
+    }
+}
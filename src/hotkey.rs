@@ -0,0 +1,33 @@
+//! Global hotkeys.
+
+use crate::input_handler::KeyCode;
+use crate::modifiers::Modifiers;
+
+/// A key pressed together with an exact set of modifiers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Hotkey {
+    /// Modifiers that must be held; lock states are ignored
+    pub modifiers: Modifiers,
+    pub key: KeyCode,
+}
+
+impl Hotkey {
+    /// Creates a hotkey for a key with the given modifiers.
+    pub fn new(modifiers: Modifiers, key: KeyCode) -> Self {
+        Hotkey { modifiers, key }
+    }
+
+    /// Checks if pressing `key` with `modifiers` held triggers this hotkey.
+    ///
+    /// Modifiers must match exactly, so Ctrl+Shift+K doesn't trigger Ctrl+K. Caps Lock and
+    /// Num Lock are ignored. When `key` is itself a modifier, such as in Ctrl+LeftShift, the
+    /// modifier it sets is left out of the comparison, since pressing it always sets it.
+    pub fn matches(&self, key: KeyCode, modifiers: Modifiers) -> bool {
+        let own = Modifiers::from_key(key);
+        self.key == key && self.modifiers.difference(own).keys() == modifiers.difference(own).keys()
+    }
+}
+
+/// Identifies a registered hotkey, to unregister it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct HotkeyId(pub(crate) u64);
@@ -9,6 +9,9 @@ pub mod windows;
 #[cfg(target_os = "macos")]
 pub mod macos;
 
+#[cfg(not(target_os = "linux"))]
+mod fallback;
+
 #[cfg(target_os = "linux")]
 pub use linux::InputHandler;
 
@@ -23,7 +26,7 @@ pub use macos::InputHandler;
 /// This enum contains the most commonly used keyboard keys. The key codes are
 /// platform-independent, and the library handles the conversion to platform-specific
 /// key codes internally.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum KeyCode {
     /// Escape key
     KeyEsc,
@@ -127,4 +130,32 @@ pub enum KeyCode {
     KeyDown,
     KeyLeft,
     KeyRight,
+    /// Right Ctrl key
+    KeyRightCtrl,
+    /// Right Alt/Option key, AltGr on many layouts
+    KeyRightAlt,
+    /// Left Super/Windows/Command key
+    KeyLeftMeta,
+    /// Right Super/Windows/Command key
+    KeyRightMeta,
+}
+
+/// A key that toggles a lock, whose state is shown by a keyboard LED.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LockKey {
+    Caps,
+    Num,
+    Scroll,
+}
+
+/// A mouse button.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MouseButton {
+    Left,
+    Right,
+    Middle,
+    /// The back button on the side of the mouse
+    Side,
+    /// The forward button on the side of the mouse
+    Extra,
 }
@@ -0,0 +1,162 @@
+//! Stand-ins for the features only the Linux backend implements so far, so that code using
+//! them builds on every platform.
+//!
+//! Modifiers are read from the keyboard. Everything else reports no devices, events or
+//! state, and settings are ignored.
+
+use std::io;
+use std::sync::mpsc::{self, Receiver};
+use std::time::{Duration, Instant};
+use crate::action::Binding;
+use crate::capture::CaptureFilter;
+use crate::event::{EventClock, InputEvent, KeyRepeat};
+use crate::gamepad::{Calibration, CalibrationStore, Gamepad, GamepadId, GamepadSettings, MappingDb};
+use crate::gesture::GestureConfig;
+use crate::hotkey::{Hotkey, HotkeyId};
+use crate::input_handler::{KeyCode, LockKey, MouseButton};
+use crate::modifiers::Modifiers;
+use crate::remap::RemapTable;
+use crate::stylus::{Stylus, StylusId};
+use crate::switch::SwitchKind;
+use crate::touch::{ScreenMapping, TouchDevice, TouchDeviceId};
+use super::InputHandler;
+
+impl InputHandler {
+    /// Checks if a mouse button is currently pressed. Always `false` on this platform.
+    pub fn is_mouse_pressed(&self, _button: MouseButton) -> bool {
+        false
+    }
+
+    /// Returns the modifier keys currently held. Lock states aren't reported on this platform.
+    pub fn modifiers(&self) -> Modifiers {
+        Modifiers::held(|key| self.is_pressed(key))
+    }
+
+    /// Returns when a key went down. Always `None` on this platform.
+    pub fn pressed_since(&self, _key: KeyCode) -> Option<Instant> {
+        None
+    }
+
+    /// Returns how long a key has been held. Always `None` on this platform.
+    pub fn held_duration(&self, _key: KeyCode) -> Option<Duration> {
+        None
+    }
+
+    /// Checks if a lock is on. Always `false` on this platform.
+    pub fn is_toggled(&self, _key: LockKey) -> bool {
+        false
+    }
+
+    /// Turns a keyboard LED on or off. Ignored on this platform.
+    pub fn set_led(&self, _key: LockKey, _on: bool) {}
+
+    /// Returns the state of a switch. Always `None` on this platform.
+    pub fn switch_state(&self, _switch: SwitchKind) -> Option<bool> {
+        None
+    }
+
+    /// Returns a snapshot of every connected gamepad. Always empty on this platform.
+    pub fn gamepads(&self) -> Vec<Gamepad> {
+        Vec::new()
+    }
+
+    /// Returns a snapshot of a single gamepad. Always `None` on this platform.
+    pub fn gamepad(&self, _id: GamepadId) -> Option<Gamepad> {
+        None
+    }
+
+    /// Sets the dead zones and response curves of a gamepad. Ignored on this platform.
+    pub fn set_gamepad_settings(&self, _id: GamepadId, _settings: GamepadSettings) {}
+
+    /// Starts calibrating a gamepad. Ignored on this platform.
+    pub fn start_calibration(&self, _id: GamepadId) {}
+
+    /// Finishes calibrating a gamepad. Always `None` on this platform.
+    pub fn finish_calibration(&self, _id: GamepadId) -> Option<Calibration> {
+        None
+    }
+
+    /// Replaces the calibration of a gamepad. Ignored on this platform.
+    pub fn set_calibration(&self, _id: GamepadId, _calibration: Calibration) {}
+
+    /// Subscribes to input events. No events are sent on this platform, so the receiver is
+    /// disconnected from the start.
+    pub fn subscribe(&self) -> Receiver<InputEvent> {
+        mpsc::channel().1
+    }
+
+    /// Registers a global hotkey. The callback never runs on this platform.
+    pub fn register_hotkey(&self, _hotkey: Hotkey, _callback: impl Fn() + Send + Sync + 'static) -> HotkeyId {
+        HotkeyId(0)
+    }
+
+    /// Unregisters a hotkey. Always `false` on this platform.
+    pub fn unregister_hotkey(&self, _id: HotkeyId) -> bool {
+        false
+    }
+
+    /// Waits for the next input to bind. Nothing is captured on this platform, so this
+    /// returns `None` immediately.
+    pub fn capture_next(&self, _filter: CaptureFilter, _timeout: Duration) -> Option<Binding> {
+        None
+    }
+
+    /// Sets the clock events are timestamped with. Ignored on this platform.
+    pub fn set_event_clock(&self, _clock: EventClock) {}
+
+    /// Returns the hardware auto-repeat timings. Always `None` on this platform.
+    pub fn hardware_repeat(&self) -> Option<KeyRepeat> {
+        None
+    }
+
+    /// Repeats held keys in software. Ignored on this platform.
+    pub fn set_software_repeat(&self, _repeat: Option<KeyRepeat>) {}
+
+    /// Sets the touchpad gesture thresholds. Ignored on this platform.
+    pub fn set_gesture_config(&self, _config: GestureConfig) {}
+
+    /// Returns a snapshot of every multitouch device. Always empty on this platform.
+    pub fn touch_devices(&self) -> Vec<TouchDevice> {
+        Vec::new()
+    }
+
+    /// Returns a snapshot of a single touch device. Always `None` on this platform.
+    pub fn touch_device(&self, _id: TouchDeviceId) -> Option<TouchDevice> {
+        None
+    }
+
+    /// Maps a touchscreen onto display pixels. Ignored on this platform.
+    pub fn set_screen_mapping(&self, _id: TouchDeviceId, _mapping: Option<ScreenMapping>) {}
+
+    /// Returns a snapshot of every drawing tablet. Always empty on this platform.
+    pub fn styluses(&self) -> Vec<Stylus> {
+        Vec::new()
+    }
+
+    /// Returns a snapshot of a single drawing tablet. Always `None` on this platform.
+    pub fn stylus(&self, _id: StylusId) -> Option<Stylus> {
+        None
+    }
+
+    /// Returns the names of the input devices. Always empty on this platform.
+    pub fn device_names(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// Replaces the key remapping table. Ignored on this platform.
+    pub fn set_remap_table(&self, _table: RemapTable) {}
+
+    /// Returns the key remapping table. Always empty on this platform.
+    pub fn remap_table(&self) -> RemapTable {
+        RemapTable::new()
+    }
+
+    /// Checks gamepad mappings in SDL `gamecontrollerdb.txt` format and returns how many there
+    /// are. They aren't applied to anything on this platform.
+    pub fn add_gamepad_mappings(&self, mappings: &str) -> io::Result<usize> {
+        MappingDb::new().add_mappings(mappings)
+    }
+
+    /// Applies stored calibrations to connected gamepads. Ignored on this platform.
+    pub fn apply_calibrations(&self, _store: &CalibrationStore) {}
+}
@@ -1,17 +1,72 @@
 //! Linux implementation using evdev for direct input device access.
 
-use evdev::{self, EventSummary};
-use std::io::ErrorKind;
+use evdev::{self, EventSummary, SynchronizationCode};
+use std::collections::HashMap;
+use std::io::{self, ErrorKind};
 use std::sync::Arc;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use parking_lot::Mutex;
-use crate::input_handler::KeyCode;
+use crate::gamepad::{Calibration, CalibrationStore, Gamepad, GamepadId, GamepadSettings, MappingDb};
+use crate::action::Binding;
+use crate::capture::{Capture, CaptureFilter, CaptureState};
+use crate::event::{EventClock, InputEvent, KeyRepeat, KeyState};
+use crate::gesture::GestureConfig;
+use crate::hotkey::{Hotkey, HotkeyId};
+use crate::input_handler::{KeyCode, LockKey, MouseButton};
+use crate::modifiers::Modifiers;
+use crate::remap::RemapTable;
+use crate::stylus::{Stylus, StylusId};
+use crate::switch::SwitchKind;
+use crate::touch::{ScreenMapping, TouchDevice, TouchDeviceId};
+
+mod clock;
+mod gamepad;
+mod stylus;
+mod touch;
+
+use gamepad::GamepadState;
+use stylus::StylusState;
+use touch::TouchState;
+
+type HotkeyCallback = Arc<dyn Fn() + Send + Sync>;
 
 const KEY_COUNT: usize = 0x300;
+const SWITCH_COUNT: usize = 0x11;
+const LED_COUNT: usize = 0x10;
 
 struct SharedState {
-    pressed_keys: [bool; KEY_COUNT],
+    /// When each held key went down, from the kernel's timestamp
+    pressed_keys: [Option<Instant>; KEY_COUNT],
+    /// How many physical keys hold each key down, across devices and remapping
+    key_holds: [u8; KEY_COUNT],
+    /// State of every switch, or `None` if no device reports it
+    switches: [Option<bool>; SWITCH_COUNT],
+    leds: [bool; LED_COUNT],
+    /// LED changes waiting to be written by the input thread
+    led_requests: Vec<(u16, bool)>,
+    gamepads: Vec<GamepadState>,
+    mappings: MappingDb,
+    touch_devices: Vec<TouchState>,
+    styluses: Vec<StylusState>,
+    subscribers: Vec<Sender<InputEvent>>,
+    /// Repeat delay and period of the first keyboard that reports them
+    hardware_repeat: Option<KeyRepeat>,
+    software_repeat: Option<KeyRepeat>,
+    /// Key being repeated in software, and when it repeats next
+    repeating: Option<(KeyCode, Instant)>,
+    /// Clock last requested, used for events generated in software
+    clock: EventClock,
+    clock_request: Option<EventClock>,
+    hotkeys: Vec<(HotkeyId, Hotkey, HotkeyCallback)>,
+    next_hotkey_id: u64,
+    /// Name of each device, by index
+    device_names: Vec<String>,
+    remap: RemapTable,
+    /// Logical codes of held keys that were remapped, by device index and physical code, so
+    /// they are released as the key they were pressed as
+    remapped: HashMap<(usize, u16), u16>,
 }
 
 /// Linux-specific input handler that reads from evdev devices.
@@ -26,6 +81,12 @@ pub struct InputHandler {
     _thread_handle: Option<thread::JoinHandle<()>>,
 }
 
+impl Default for InputHandler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl InputHandler {
     /// Creates a new input handler and starts a background thread to monitor input devices.
     ///
@@ -37,8 +98,72 @@ impl InputHandler {
             device
         }).collect();
 
+        let mappings = MappingDb::new();
+        let gamepads = devices.iter().enumerate()
+            .filter_map(|(index, device)| GamepadState::detect(index, device, &mappings))
+            .collect();
+        let touch_devices = devices.iter().enumerate()
+            .filter_map(|(index, device)| TouchState::detect(index, device, GestureConfig::default()))
+            .collect();
+        let styluses = devices.iter().enumerate()
+            .filter_map(|(index, device)| StylusState::detect(index, device))
+            .collect();
+
+        let mut switches = [None; SWITCH_COUNT];
+        for device in &devices {
+            if let Some(supported) = device.supported_switches() {
+                let active = device.get_switch_state().unwrap_or_default();
+                for switch in supported.iter() {
+                    if let Some(state) = switches.get_mut(switch.0 as usize) {
+                        *state = Some(active.contains(switch));
+                    }
+                }
+            }
+        }
+
+        let mut leds = [false; LED_COUNT];
+        for device in &devices {
+            if let Ok(lit) = device.get_led_state() {
+                for led in lit.iter() {
+                    if let Some(state) = leds.get_mut(led.0 as usize) {
+                        *state = true;
+                    }
+                }
+            }
+        }
+
+        let device_names = devices.iter()
+            .map(|device| device.name().unwrap_or("Unknown device").to_string())
+            .collect();
+
+        let hardware_repeat = devices.iter()
+            .find_map(|device| device.get_auto_repeat())
+            .map(|repeat| KeyRepeat {
+                delay: Duration::from_millis(repeat.delay.into()),
+                period: Duration::from_millis(repeat.period.into()),
+            });
+
         let state = Arc::new(Mutex::new(SharedState {
-            pressed_keys: [false; KEY_COUNT],
+            pressed_keys: [None; KEY_COUNT],
+            key_holds: [0; KEY_COUNT],
+            switches,
+            leds,
+            led_requests: Vec::new(),
+            gamepads,
+            mappings,
+            touch_devices,
+            styluses,
+            subscribers: Vec::new(),
+            hardware_repeat,
+            software_repeat: None,
+            repeating: None,
+            clock: EventClock::default(),
+            clock_request: None,
+            hotkeys: Vec::new(),
+            next_hotkey_id: 0,
+            device_names,
+            remap: RemapTable::new(),
+            remapped: HashMap::new(),
         }));
 
         let state_clone = Arc::clone(&state);
@@ -53,22 +178,150 @@ impl InputHandler {
     }
 
     fn input_thread(mut devices: Vec<evdev::Device>, state: Arc<Mutex<SharedState>>) {
+        // Devices that refuse a new clock keep timestamping with the previous one.
+        let mut device_clocks = vec![EventClock::default(); devices.len()];
         loop {
             let mut state_guard = state.lock();
-            for device in &mut devices {
+            let state_ref = &mut *state_guard;
+            let mut pending = Vec::new();
+            let mut fired: Vec<HotkeyCallback> = Vec::new();
+            if let Some(clock) = state_ref.clock_request.take() {
+                for (device, device_clock) in devices.iter().zip(&mut device_clocks) {
+                    match clock::set_clock(device, clock) {
+                        Ok(()) => *device_clock = clock,
+                        Err(err) => eprintln!("Failed to set input device clock: {}", err),
+                    }
+                }
+                state_ref.clock = clock;
+            }
+            for (code, on) in std::mem::take(&mut state_ref.led_requests) {
+                Self::write_led(&mut devices, code, on);
+                state_ref.leds[code as usize] = on;
+            }
+            for (index, device) in devices.iter_mut().enumerate() {
+                let mut gamepad = state_ref.gamepads.iter_mut()
+                    .find(|gamepad| gamepad.device_index == index);
+                let mut touch = state_ref.touch_devices.iter_mut().enumerate()
+                    .find(|(_, touch)| touch.device_index == index);
+                let mut stylus = state_ref.styluses.iter_mut()
+                    .find(|stylus| stylus.device_index == index);
                 match device.fetch_events() {
                     Ok(events) => {
                         for event in events {
-                            match event.destructure() {
+                            let time = clock::event_time(&event, device_clocks[index]);
+                            let summary = match event.destructure() {
+                                EventSummary::Key(event, key_type, value) => {
+                                    let code = Self::remap_code(
+                                        &state_ref.remap,
+                                        &state_ref.device_names[index],
+                                        &mut state_ref.remapped,
+                                        (index, key_type.code()),
+                                        value,
+                                    );
+                                    EventSummary::Key(event, evdev::KeyCode(code), value)
+                                }
+                                summary => summary,
+                            };
+                            match summary {
                                 EventSummary::Key(_, key_type, 1) => {
-                                    state_guard.pressed_keys[key_type.code() as usize] = true;
+                                    if let Some(gamepad) = gamepad.as_deref_mut() {
+                                        gamepad.set_button(key_type.code(), true);
+                                    }
+                                    if let Some(stylus) = stylus.as_deref_mut() {
+                                        stylus.handle_key(key_type.code(), true);
+                                    }
+                                    // Another physical key already holds this key down.
+                                    let holds = &mut state_ref.key_holds[key_type.code() as usize];
+                                    *holds = holds.saturating_add(1);
+                                    if *holds > 1 {
+                                        continue;
+                                    }
+                                    state_ref.pressed_keys[key_type.code() as usize] = Some(time.instant());
+                                    if let Some(key) = Self::from_evdev_code(key_type.code()) {
+                                        let modifiers = Self::modifiers_of(&state_ref.pressed_keys, &state_ref.leds);
+                                        pending.push(InputEvent::Key { key, state: KeyState::Pressed, modifiers, time });
+                                        fired.extend(state_ref.hotkeys.iter()
+                                            .filter(|(_, hotkey, _)| hotkey.matches(key, modifiers))
+                                            .map(|(_, _, callback)| Arc::clone(callback)));
+                                        if let Some(repeat) = state_ref.software_repeat {
+                                            state_ref.repeating = Some((key, time.instant() + repeat.delay));
+                                        }
+                                    }
+                                    if let Some(button) = Self::from_evdev_button(key_type.code()) {
+                                        pending.push(InputEvent::MouseButton { button, state: KeyState::Pressed, time });
+                                    }
                                 }
                                 EventSummary::Key(_, key_type, 0) => {
-                                    state_guard.pressed_keys[key_type.code() as usize] = false;
+                                    if let Some(gamepad) = gamepad.as_deref_mut() {
+                                        gamepad.set_button(key_type.code(), false);
+                                    }
+                                    if let Some(stylus) = stylus.as_deref_mut() {
+                                        stylus.handle_key(key_type.code(), false);
+                                    }
+                                    // The key stays down while another physical key holds it.
+                                    let holds = &mut state_ref.key_holds[key_type.code() as usize];
+                                    *holds = holds.saturating_sub(1);
+                                    if *holds > 0 {
+                                        continue;
+                                    }
+                                    state_ref.pressed_keys[key_type.code() as usize] = None;
+                                    if let Some(key) = Self::from_evdev_code(key_type.code()) {
+                                        let modifiers = Self::modifiers_of(&state_ref.pressed_keys, &state_ref.leds);
+                                        pending.push(InputEvent::Key { key, state: KeyState::Released, modifiers, time });
+                                        if state_ref.repeating.is_some_and(|(repeating, _)| repeating == key) {
+                                            state_ref.repeating = None;
+                                        }
+                                    }
+                                    if let Some(button) = Self::from_evdev_button(key_type.code()) {
+                                        pending.push(InputEvent::MouseButton { button, state: KeyState::Released, time });
+                                    }
+                                },
+                                EventSummary::Key(_, key_type, 2) => {
+                                    if state_ref.software_repeat.is_none()
+                                        && let Some(key) = Self::from_evdev_code(key_type.code())
+                                    {
+                                        let modifiers = Self::modifiers_of(&state_ref.pressed_keys, &state_ref.leds);
+                                        pending.push(InputEvent::Key { key, state: KeyState::Repeat, modifiers, time });
+                                    }
+                                },
+                                EventSummary::AbsoluteAxis(_, axis, value) => {
+                                    if let Some(gamepad) = gamepad.as_deref_mut() {
+                                        gamepad.set_axis(axis.0, value);
+                                    }
+                                    if let Some((_, touch)) = touch.as_mut() {
+                                        touch.handle_axis(axis, value);
+                                    }
+                                    if let Some(stylus) = stylus.as_deref_mut() {
+                                        stylus.handle_axis(axis, value);
+                                    }
+                                },
+                                EventSummary::Switch(_, switch, value) => {
+                                    if let Some(state) = state_ref.switches.get_mut(switch.0 as usize) {
+                                        *state = Some(value != 0);
+                                    }
+                                    if let Some(switch) = Self::from_evdev_switch(switch.0) {
+                                        pending.push(InputEvent::Switch { switch, active: value != 0, time });
+                                    }
+                                },
+                                EventSummary::Led(_, led, value) => {
+                                    if let Some(state) = state_ref.leds.get_mut(led.0 as usize) {
+                                        *state = value != 0;
+                                    }
+                                },
+                                EventSummary::Synchronization(_, SynchronizationCode::SYN_REPORT, _) => {
+                                    if let Some((touch_index, touch)) = touch.as_mut() {
+                                        let device = TouchDeviceId(*touch_index);
+                                        for gesture in touch.end_frame(time.instant()) {
+                                            pending.push(InputEvent::Gesture { device, gesture, time });
+                                        }
+                                    }
                                 },
                                 _ => {}
                             }
                         }
+                        if let Some(gamepad) = gamepad {
+                            gamepad.update_triggers();
+                        }
                     },
                     Err(err) => {
                         if err.kind() != ErrorKind::WouldBlock {
@@ -77,7 +330,23 @@ impl InputHandler {
                     }
                 }
             }
+            if let (Some(repeat), Some((key, next))) = (state_ref.software_repeat, state_ref.repeating.as_mut()) {
+                let now = Instant::now();
+                // A stalled thread repeats once when it catches up rather than in a burst.
+                if *next <= now {
+                    let time = clock::time_at(now, state_ref.clock);
+                    let modifiers = Self::modifiers_of(&state_ref.pressed_keys, &state_ref.leds);
+                    pending.push(InputEvent::Key { key: *key, state: KeyState::Repeat, modifiers, time });
+                    *next = now + repeat.period.max(Duration::from_millis(1));
+                }
+            }
+            state_ref.subscribers.retain(|subscriber| {
+                pending.iter().all(|event| subscriber.send(event.clone()).is_ok())
+            });
             drop(state_guard);
+            for callback in fired {
+                callback();
+            }
             thread::sleep(Duration::from_millis(5));
         }
     }
@@ -94,7 +363,324 @@ impl InputHandler {
     pub fn is_pressed(&self, key: KeyCode) -> bool {
         let evdev_code = Self::to_evdev_code(key);
         let state = self.state.lock();
-        state.pressed_keys[evdev_code as usize]
+        state.pressed_keys[evdev_code as usize].is_some()
+    }
+
+    /// Checks if a mouse button is currently pressed, on any mouse or touchpad.
+    pub fn is_mouse_pressed(&self, button: MouseButton) -> bool {
+        let evdev_code = Self::to_evdev_button(button);
+        self.state.lock().pressed_keys[evdev_code as usize].is_some()
+    }
+
+    /// Returns the modifier keys currently held and the lock keys toggled on.
+    pub fn modifiers(&self) -> Modifiers {
+        let state = self.state.lock();
+        Self::modifiers_of(&state.pressed_keys, &state.leds)
+    }
+
+    /// Returns when a key was pressed, or `None` if it isn't held.
+    ///
+    /// The time comes from the kernel's timestamp of the key event, so it isn't delayed by
+    /// the background thread's polling.
+    pub fn pressed_since(&self, key: KeyCode) -> Option<Instant> {
+        let evdev_code = Self::to_evdev_code(key);
+        self.state.lock().pressed_keys[evdev_code as usize]
+    }
+
+    /// Returns how long a key has been held, or `None` if it isn't held.
+    pub fn held_duration(&self, key: KeyCode) -> Option<Duration> {
+        self.pressed_since(key).map(|since| Instant::now().saturating_duration_since(since))
+    }
+
+    /// Checks if a lock key is toggled on, according to the keyboard LEDs.
+    ///
+    /// Unlike [`is_pressed`](Self::is_pressed) with [`KeyCode::KeyCapslock`], this reports
+    /// whether Caps Lock is on rather than whether the key is held.
+    pub fn is_toggled(&self, key: LockKey) -> bool {
+        let state = self.state.lock();
+        state.leds[Self::to_evdev_led(key) as usize]
+    }
+
+    /// Turns the LED of a lock key on or off on every keyboard that has it.
+    ///
+    /// Only the LED changes; the lock itself stays as the system keyboard layout has it, and
+    /// the LED may be reset when the lock key is next pressed.
+    pub fn set_led(&self, key: LockKey, on: bool) {
+        self.state.lock().led_requests.push((Self::to_evdev_led(key), on));
+    }
+
+    /// Returns whether a switch is active, or `None` if no device reports it.
+    ///
+    /// Initial states are read when the handler is created; changes are also delivered as
+    /// [`InputEvent::Switch`] to [subscribers](Self::subscribe).
+    pub fn switch_state(&self, switch: SwitchKind) -> Option<bool> {
+        let state = self.state.lock();
+        state.switches[Self::to_evdev_switch(switch) as usize]
+    }
+
+    /// Returns a snapshot of every connected gamepad and joystick.
+    ///
+    /// Devices are detected when the handler is created, by looking for the gamepad
+    /// (`BTN_SOUTH`) or joystick (`BTN_TRIGGER`) button ranges.
+    pub fn gamepads(&self) -> Vec<Gamepad> {
+        let state = self.state.lock();
+        state.gamepads.iter().enumerate()
+            .map(|(index, gamepad)| gamepad.snapshot(GamepadId(index)))
+            .collect()
+    }
+
+    /// Returns a snapshot of a single gamepad, or `None` if the id is unknown.
+    pub fn gamepad(&self, id: GamepadId) -> Option<Gamepad> {
+        let state = self.state.lock();
+        state.gamepads.get(id.0).map(|gamepad| gamepad.snapshot(id))
+    }
+
+    /// Sets the dead zones, saturation and response curves applied to a gamepad's axes.
+    pub fn set_gamepad_settings(&self, id: GamepadId, settings: GamepadSettings) {
+        if let Some(gamepad) = self.state.lock().gamepads.get_mut(id.0) {
+            gamepad.settings = settings;
+        }
+    }
+
+    /// Starts calibrating a gamepad.
+    ///
+    /// The current position of every axis is taken as its center, so the sticks should be at
+    /// rest. Afterwards, the user should move every axis to its extremes before
+    /// [`finish_calibration`](Self::finish_calibration) is called.
+    pub fn start_calibration(&self, id: GamepadId) {
+        if let Some(gamepad) = self.state.lock().gamepads.get_mut(id.0) {
+            gamepad.start_calibration();
+        }
+    }
+
+    /// Finishes calibrating a gamepad, applies the recorded ranges and returns them.
+    ///
+    /// Returns `None` if the gamepad is unknown or no calibration was started.
+    pub fn finish_calibration(&self, id: GamepadId) -> Option<Calibration> {
+        self.state.lock().gamepads.get_mut(id.0)?.finish_calibration()
+    }
+
+    /// Replaces the calibration of a gamepad. An empty calibration restores the ranges the
+    /// device advertises.
+    pub fn set_calibration(&self, id: GamepadId, calibration: Calibration) {
+        if let Some(gamepad) = self.state.lock().gamepads.get_mut(id.0) {
+            gamepad.calibration = calibration;
+        }
+    }
+
+    /// Subscribes to the events seen by the background thread.
+    ///
+    /// Every subscriber receives every event from the moment it subscribed. Events queue up in
+    /// the returned receiver until they are read, so it should be drained regularly or dropped
+    /// once no longer needed.
+    pub fn subscribe(&self) -> Receiver<InputEvent> {
+        let (sender, receiver) = mpsc::channel();
+        self.state.lock().subscribers.push(sender);
+        receiver
+    }
+
+    /// Registers a global hotkey and returns an id to unregister it with.
+    ///
+    /// The callback runs on the background thread whenever the hotkey's key is pressed with
+    /// exactly its modifiers held, so it should return quickly. It runs after the handler's
+    /// state is unlocked and may call back into the handler. Keys aren't grabbed, so other
+    /// applications still see them.
+    pub fn register_hotkey(&self, hotkey: Hotkey, callback: impl Fn() + Send + Sync + 'static) -> HotkeyId {
+        let mut state = self.state.lock();
+        let id = HotkeyId(state.next_hotkey_id);
+        state.next_hotkey_id += 1;
+        state.hotkeys.push((id, hotkey, Arc::new(callback)));
+        id
+    }
+
+    /// Unregisters a hotkey. Returns `false` if it wasn't registered.
+    pub fn unregister_hotkey(&self, id: HotkeyId) -> bool {
+        let mut state = self.state.lock();
+        let count = state.hotkeys.len();
+        state.hotkeys.retain(|(registered, _, _)| *registered != id);
+        state.hotkeys.len() != count
+    }
+
+    /// Waits for the next key, mouse button or gamepad input and returns a binding for it, for
+    /// "press a key to bind" prompts. Returns `None` if nothing was captured within `timeout`.
+    ///
+    /// Keys and buttons held when the capture starts are ignored until they are released, and
+    /// axes until they return to rest, so the key that opened the prompt isn't captured. Keys
+    /// pressed with modifiers are captured as a [`Binding::Hotkey`], while a modifier key on its
+    /// own is captured when it is released. This blocks the calling thread.
+    pub fn capture_next(&self, filter: CaptureFilter, timeout: Duration) -> Option<Binding> {
+        let deadline = Instant::now() + timeout;
+        // Subscribing under the same lock as the snapshot means no press falls between them.
+        let (events, mut held) = {
+            let mut state = self.state.lock();
+            let (sender, receiver) = mpsc::channel();
+            state.subscribers.push(sender);
+            let mut held = CaptureState::default();
+            for code in (0..KEY_COUNT as u16).filter(|code| state.pressed_keys[*code as usize].is_some()) {
+                held.keys.extend(Self::from_evdev_code(code));
+                held.mouse.extend(Self::from_evdev_button(code));
+            }
+            Self::poll_gamepads(&state, &mut held);
+            (receiver, held)
+        };
+        let mut capture = Capture::new(filter, &held);
+        loop {
+            let remaining = deadline.checked_duration_since(Instant::now())?;
+            // Gamepads don't send events, so they are polled between key and mouse events.
+            match events.recv_timeout(remaining.min(Duration::from_millis(5))) {
+                Ok(InputEvent::Key { key, state, .. }) => Self::track_held(&mut held.keys, key, state),
+                Ok(InputEvent::MouseButton { button, state, .. }) => Self::track_held(&mut held.mouse, button, state),
+                Ok(_) | Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => return None,
+            }
+            Self::poll_gamepads(&self.state.lock(), &mut held);
+            if let Some(binding) = capture.check(&held) {
+                return Some(binding);
+            }
+        }
+    }
+
+    fn track_held<T: PartialEq>(held: &mut Vec<T>, input: T, state: KeyState) {
+        match state {
+            KeyState::Pressed if !held.contains(&input) => held.push(input),
+            KeyState::Released => held.retain(|other| *other != input),
+            _ => {}
+        }
+    }
+
+    fn poll_gamepads(state: &SharedState, held: &mut CaptureState) {
+        let gamepads: Vec<_> = state.gamepads.iter().enumerate()
+            .map(|(index, gamepad)| gamepad.snapshot(GamepadId(index)))
+            .collect();
+        held.set_gamepads(&gamepads);
+    }
+
+    /// Sets the clock the kernel timestamps events with on every device.
+    ///
+    /// [`EventClock::Monotonic`] makes [`EventTime::instant`](crate::EventTime::instant)
+    /// exact even when the system time changes. Events queued before the switch are dropped by
+    /// the kernel. A device that refuses the clock keeps its previous one, which its events
+    /// report through [`EventTime::clock`](crate::EventTime::clock).
+    pub fn set_event_clock(&self, clock: EventClock) {
+        self.state.lock().clock_request = Some(clock);
+    }
+
+    /// Returns the auto-repeat delay and period the kernel uses for held keys, or `None` if no
+    /// keyboard reports them.
+    ///
+    /// The values are read when the handler is created.
+    pub fn hardware_repeat(&self) -> Option<KeyRepeat> {
+        self.state.lock().hardware_repeat
+    }
+
+    /// Repeats held keys in software with the given delay and period, or restores the kernel's
+    /// repeats with `None`.
+    ///
+    /// While software repeat is enabled, repeats reported by the kernel are dropped, and only
+    /// the most recently pressed key repeats, as with hardware repeat. Repeats missed while the
+    /// input thread was busy are skipped rather than sent in a burst.
+    pub fn set_software_repeat(&self, repeat: Option<KeyRepeat>) {
+        let mut state = self.state.lock();
+        state.software_repeat = repeat;
+        state.repeating = None;
+    }
+
+    /// Sets the thresholds used to recognize touchpad gestures on every touch device.
+    pub fn set_gesture_config(&self, config: GestureConfig) {
+        for touch in &mut self.state.lock().touch_devices {
+            touch.set_gesture_config(config);
+        }
+    }
+
+    /// Returns a snapshot of every connected multitouch device.
+    ///
+    /// Devices are detected when the handler is created, by looking for multitouch slots
+    /// (`ABS_MT_SLOT`).
+    pub fn touch_devices(&self) -> Vec<TouchDevice> {
+        let state = self.state.lock();
+        state.touch_devices.iter().enumerate()
+            .map(|(index, touch)| touch.snapshot(TouchDeviceId(index)))
+            .collect()
+    }
+
+    /// Returns a snapshot of a single touch device, or `None` if the id is unknown.
+    pub fn touch_device(&self, id: TouchDeviceId) -> Option<TouchDevice> {
+        let state = self.state.lock();
+        state.touch_devices.get(id.0).map(|touch| touch.snapshot(id))
+    }
+
+    /// Sets how a touchscreen's positions map onto display pixels, or removes the mapping
+    /// with `None`.
+    ///
+    /// Mapped positions are available from [`TouchDevice::screen_position`].
+    pub fn set_screen_mapping(&self, id: TouchDeviceId, mapping: Option<ScreenMapping>) {
+        if let Some(touch) = self.state.lock().touch_devices.get_mut(id.0) {
+            touch.mapping = mapping;
+        }
+    }
+
+    /// Returns a snapshot of every connected drawing tablet.
+    ///
+    /// Devices are detected when the handler is created, by looking for a pen tool
+    /// (`BTN_TOOL_PEN`) with absolute position axes.
+    pub fn styluses(&self) -> Vec<Stylus> {
+        let state = self.state.lock();
+        state.styluses.iter().enumerate()
+            .map(|(index, stylus)| stylus.snapshot(StylusId(index)))
+            .collect()
+    }
+
+    /// Returns a snapshot of a single drawing tablet, or `None` if the id is unknown.
+    pub fn stylus(&self, id: StylusId) -> Option<Stylus> {
+        let state = self.state.lock();
+        state.styluses.get(id.0).map(|stylus| stylus.snapshot(id))
+    }
+
+    /// Returns the names of the input devices, which [`RemapTable`] rules can be scoped to.
+    pub fn device_names(&self) -> Vec<String> {
+        self.state.lock().device_names.clone()
+    }
+
+    /// Replaces the key remapping table. Keys are remapped as they are read, so
+    /// [`is_pressed`](Self::is_pressed), events and hotkeys all see the logical keys.
+    ///
+    /// Keys held while the table changes are released as the key they were pressed as. When
+    /// two physical keys act as the same key, it stays down until both are released.
+    ///
+    /// The kernel still toggles the Caps Lock LED when the physical Caps Lock key is pressed,
+    /// so [`is_toggled`](Self::is_toggled) with [`LockKey::Caps`] and
+    /// [`Modifiers::CAPS_LOCK`] still change when Caps Lock is remapped to another key.
+    pub fn set_remap_table(&self, table: RemapTable) {
+        self.state.lock().remap = table;
+    }
+
+    /// Returns the key remapping table.
+    pub fn remap_table(&self) -> RemapTable {
+        self.state.lock().remap.clone()
+    }
+
+    /// Adds gamepad mappings in SDL `gamecontrollerdb.txt` format and returns how many were
+    /// added.
+    ///
+    /// Added mappings take precedence over the built-in ones and over mappings added earlier,
+    /// and are applied to connected gamepads immediately.
+    pub fn add_gamepad_mappings(&self, mappings: &str) -> io::Result<usize> {
+        let mut state = self.state.lock();
+        let added = state.mappings.add_mappings(mappings)?;
+        let SharedState { gamepads, mappings, .. } = &mut *state;
+        for gamepad in gamepads {
+            gamepad.update_mapping(mappings);
+        }
+        Ok(added)
+    }
+
+    /// Applies stored calibrations to every connected gamepad with a matching identity.
+    pub fn apply_calibrations(&self, store: &CalibrationStore) {
+        for gamepad in &mut self.state.lock().gamepads {
+            if let Some(calibration) = store.get(gamepad.identity()) {
+                gamepad.calibration = calibration.clone();
+            }
+        }
     }
 
     fn to_evdev_code(key: KeyCode) -> u16 {
@@ -174,7 +760,214 @@ impl InputHandler {
             KeyCode::KeyDown => EvKeyCode::KEY_DOWN.code(),
             KeyCode::KeyLeft => EvKeyCode::KEY_LEFT.code(),
             KeyCode::KeyRight => EvKeyCode::KEY_RIGHT.code(),
+            KeyCode::KeyRightCtrl => EvKeyCode::KEY_RIGHTCTRL.code(),
+            KeyCode::KeyRightAlt => EvKeyCode::KEY_RIGHTALT.code(),
+            KeyCode::KeyLeftMeta => EvKeyCode::KEY_LEFTMETA.code(),
+            KeyCode::KeyRightMeta => EvKeyCode::KEY_RIGHTMETA.code(),
         }
     }
-}
 
+    /// Returns the logical code of a key event from a device, applying the remap table when
+    /// the key goes down and reusing that mapping until it is released.
+    fn remap_code(
+        table: &RemapTable,
+        device: &str,
+        remapped: &mut HashMap<(usize, u16), u16>,
+        (device_index, code): (usize, u16),
+        value: i32,
+    ) -> u16 {
+        match value {
+            1 => {
+                let logical = Self::from_evdev_code(code)
+                    .map_or(code, |key| Self::to_evdev_code(table.map(device, key)));
+                if logical != code {
+                    remapped.insert((device_index, code), logical);
+                }
+                logical
+            }
+            0 => remapped.remove(&(device_index, code)).unwrap_or(code),
+            _ => remapped.get(&(device_index, code)).copied().unwrap_or(code),
+        }
+    }
+
+    fn modifiers_of(pressed_keys: &[Option<Instant>; KEY_COUNT], leds: &[bool; LED_COUNT]) -> Modifiers {
+        let mut modifiers = Modifiers::held(|key| pressed_keys[Self::to_evdev_code(key) as usize].is_some());
+        modifiers.set(Modifiers::CAPS_LOCK, leds[Self::to_evdev_led(LockKey::Caps) as usize]);
+        modifiers.set(Modifiers::NUM_LOCK, leds[Self::to_evdev_led(LockKey::Num) as usize]);
+        modifiers
+    }
+
+    fn write_led(devices: &mut [evdev::Device], code: u16, on: bool) {
+        let event = evdev::InputEvent::new(evdev::EventType::LED.0, code, on as i32);
+        for device in devices {
+            let supported = device.supported_leds().is_some_and(|leds| leds.contains(evdev::LedCode(code)));
+            if supported && let Err(err) = device.send_events(&[event]) {
+                eprintln!("Failed to set keyboard LED: {}", err);
+            }
+        }
+    }
+
+    fn to_evdev_button(button: MouseButton) -> u16 {
+        use evdev::KeyCode as EvKeyCode;
+        match button {
+            MouseButton::Left => EvKeyCode::BTN_LEFT.code(),
+            MouseButton::Right => EvKeyCode::BTN_RIGHT.code(),
+            MouseButton::Middle => EvKeyCode::BTN_MIDDLE.code(),
+            MouseButton::Side => EvKeyCode::BTN_SIDE.code(),
+            MouseButton::Extra => EvKeyCode::BTN_EXTRA.code(),
+        }
+    }
+
+    fn from_evdev_button(code: u16) -> Option<MouseButton> {
+        use evdev::KeyCode as EvKeyCode;
+        Some(match EvKeyCode(code) {
+            EvKeyCode::BTN_LEFT => MouseButton::Left,
+            EvKeyCode::BTN_RIGHT => MouseButton::Right,
+            EvKeyCode::BTN_MIDDLE => MouseButton::Middle,
+            EvKeyCode::BTN_SIDE => MouseButton::Side,
+            EvKeyCode::BTN_EXTRA => MouseButton::Extra,
+            _ => return None,
+        })
+    }
+
+    fn to_evdev_led(key: LockKey) -> u16 {
+        use evdev::LedCode;
+        match key {
+            LockKey::Caps => LedCode::LED_CAPSL.0,
+            LockKey::Num => LedCode::LED_NUML.0,
+            LockKey::Scroll => LedCode::LED_SCROLLL.0,
+        }
+    }
+
+    fn to_evdev_switch(switch: SwitchKind) -> u16 {
+        use evdev::SwitchCode;
+        match switch {
+            SwitchKind::Lid => SwitchCode::SW_LID.0,
+            SwitchKind::TabletMode => SwitchCode::SW_TABLET_MODE.0,
+            SwitchKind::HeadphoneInserted => SwitchCode::SW_HEADPHONE_INSERT.0,
+            SwitchKind::RadiosDisabled => SwitchCode::SW_RFKILL_ALL.0,
+            SwitchKind::MicrophoneInserted => SwitchCode::SW_MICROPHONE_INSERT.0,
+            SwitchKind::Docked => SwitchCode::SW_DOCK.0,
+            SwitchKind::LineOutInserted => SwitchCode::SW_LINEOUT_INSERT.0,
+            SwitchKind::JackInserted => SwitchCode::SW_JACK_PHYSICAL_INSERT.0,
+            SwitchKind::VideoOutInserted => SwitchCode::SW_VIDEOOUT_INSERT.0,
+            SwitchKind::CameraLensCovered => SwitchCode::SW_CAMERA_LENS_COVER.0,
+            SwitchKind::KeypadSlide => SwitchCode::SW_KEYPAD_SLIDE.0,
+            SwitchKind::FrontProximity => SwitchCode::SW_FRONT_PROXIMITY.0,
+            SwitchKind::RotateLock => SwitchCode::SW_ROTATE_LOCK.0,
+            SwitchKind::LineInInserted => SwitchCode::SW_LINEIN_INSERT.0,
+            SwitchKind::Muted => SwitchCode::SW_MUTE_DEVICE.0,
+            SwitchKind::PenInserted => SwitchCode::SW_PEN_INSERTED.0,
+            SwitchKind::MachineCover => SwitchCode::SW_MACHINE_COVER.0,
+        }
+    }
+
+    fn from_evdev_switch(code: u16) -> Option<SwitchKind> {
+        use evdev::SwitchCode;
+        Some(match SwitchCode(code) {
+            SwitchCode::SW_LID => SwitchKind::Lid,
+            SwitchCode::SW_TABLET_MODE => SwitchKind::TabletMode,
+            SwitchCode::SW_HEADPHONE_INSERT => SwitchKind::HeadphoneInserted,
+            SwitchCode::SW_RFKILL_ALL => SwitchKind::RadiosDisabled,
+            SwitchCode::SW_MICROPHONE_INSERT => SwitchKind::MicrophoneInserted,
+            SwitchCode::SW_DOCK => SwitchKind::Docked,
+            SwitchCode::SW_LINEOUT_INSERT => SwitchKind::LineOutInserted,
+            SwitchCode::SW_JACK_PHYSICAL_INSERT => SwitchKind::JackInserted,
+            SwitchCode::SW_VIDEOOUT_INSERT => SwitchKind::VideoOutInserted,
+            SwitchCode::SW_CAMERA_LENS_COVER => SwitchKind::CameraLensCovered,
+            SwitchCode::SW_KEYPAD_SLIDE => SwitchKind::KeypadSlide,
+            SwitchCode::SW_FRONT_PROXIMITY => SwitchKind::FrontProximity,
+            SwitchCode::SW_ROTATE_LOCK => SwitchKind::RotateLock,
+            SwitchCode::SW_LINEIN_INSERT => SwitchKind::LineInInserted,
+            SwitchCode::SW_MUTE_DEVICE => SwitchKind::Muted,
+            SwitchCode::SW_PEN_INSERTED => SwitchKind::PenInserted,
+            SwitchCode::SW_MACHINE_COVER => SwitchKind::MachineCover,
+            _ => return None,
+        })
+    }
+
+    fn from_evdev_code(code: u16) -> Option<KeyCode> {
+        use evdev::KeyCode as EvKeyCode;
+        Some(match EvKeyCode(code) {
+            EvKeyCode::KEY_ESC => KeyCode::KeyEsc,
+            EvKeyCode::KEY_1 => KeyCode::Key1,
+            EvKeyCode::KEY_2 => KeyCode::Key2,
+            EvKeyCode::KEY_3 => KeyCode::Key3,
+            EvKeyCode::KEY_4 => KeyCode::Key4,
+            EvKeyCode::KEY_5 => KeyCode::Key5,
+            EvKeyCode::KEY_6 => KeyCode::Key6,
+            EvKeyCode::KEY_7 => KeyCode::Key7,
+            EvKeyCode::KEY_8 => KeyCode::Key8,
+            EvKeyCode::KEY_9 => KeyCode::Key9,
+            EvKeyCode::KEY_0 => KeyCode::Key0,
+            EvKeyCode::KEY_MINUS => KeyCode::KeyMinus,
+            EvKeyCode::KEY_EQUAL => KeyCode::KeyEqual,
+            EvKeyCode::KEY_BACKSPACE => KeyCode::KeyBackspace,
+            EvKeyCode::KEY_TAB => KeyCode::KeyTab,
+            EvKeyCode::KEY_Q => KeyCode::KeyQ,
+            EvKeyCode::KEY_W => KeyCode::KeyW,
+            EvKeyCode::KEY_E => KeyCode::KeyE,
+            EvKeyCode::KEY_R => KeyCode::KeyR,
+            EvKeyCode::KEY_T => KeyCode::KeyT,
+            EvKeyCode::KEY_Y => KeyCode::KeyY,
+            EvKeyCode::KEY_U => KeyCode::KeyU,
+            EvKeyCode::KEY_I => KeyCode::KeyI,
+            EvKeyCode::KEY_O => KeyCode::KeyO,
+            EvKeyCode::KEY_P => KeyCode::KeyP,
+            EvKeyCode::KEY_LEFTBRACE => KeyCode::KeyLeftBrace,
+            EvKeyCode::KEY_RIGHTBRACE => KeyCode::KeyRightBrace,
+            EvKeyCode::KEY_ENTER => KeyCode::KeyEnter,
+            EvKeyCode::KEY_LEFTCTRL => KeyCode::KeyLeftCtrl,
+            EvKeyCode::KEY_A => KeyCode::KeyA,
+            EvKeyCode::KEY_S => KeyCode::KeyS,
+            EvKeyCode::KEY_D => KeyCode::KeyD,
+            EvKeyCode::KEY_F => KeyCode::KeyF,
+            EvKeyCode::KEY_G => KeyCode::KeyG,
+            EvKeyCode::KEY_H => KeyCode::KeyH,
+            EvKeyCode::KEY_J => KeyCode::KeyJ,
+            EvKeyCode::KEY_K => KeyCode::KeyK,
+            EvKeyCode::KEY_L => KeyCode::KeyL,
+            EvKeyCode::KEY_SEMICOLON => KeyCode::KeySemicolon,
+            EvKeyCode::KEY_APOSTROPHE => KeyCode::KeyApostrophe,
+            EvKeyCode::KEY_GRAVE => KeyCode::KeyGrave,
+            EvKeyCode::KEY_LEFTSHIFT => KeyCode::KeyLeftShift,
+            EvKeyCode::KEY_BACKSLASH => KeyCode::KeyBackslash,
+            EvKeyCode::KEY_Z => KeyCode::KeyZ,
+            EvKeyCode::KEY_X => KeyCode::KeyX,
+            EvKeyCode::KEY_C => KeyCode::KeyC,
+            EvKeyCode::KEY_V => KeyCode::KeyV,
+            EvKeyCode::KEY_B => KeyCode::KeyB,
+            EvKeyCode::KEY_N => KeyCode::KeyN,
+            EvKeyCode::KEY_M => KeyCode::KeyM,
+            EvKeyCode::KEY_COMMA => KeyCode::KeyComma,
+            EvKeyCode::KEY_DOT => KeyCode::KeyDot,
+            EvKeyCode::KEY_SLASH => KeyCode::KeySlash,
+            EvKeyCode::KEY_RIGHTSHIFT => KeyCode::KeyRightShift,
+            EvKeyCode::KEY_KPASTERISK => KeyCode::KeyKpAsterisk,
+            EvKeyCode::KEY_LEFTALT => KeyCode::KeyLeftAlt,
+            EvKeyCode::KEY_SPACE => KeyCode::KeySpace,
+            EvKeyCode::KEY_CAPSLOCK => KeyCode::KeyCapslock,
+            EvKeyCode::KEY_F1 => KeyCode::KeyF1,
+            EvKeyCode::KEY_F2 => KeyCode::KeyF2,
+            EvKeyCode::KEY_F3 => KeyCode::KeyF3,
+            EvKeyCode::KEY_F4 => KeyCode::KeyF4,
+            EvKeyCode::KEY_F5 => KeyCode::KeyF5,
+            EvKeyCode::KEY_F6 => KeyCode::KeyF6,
+            EvKeyCode::KEY_F7 => KeyCode::KeyF7,
+            EvKeyCode::KEY_F8 => KeyCode::KeyF8,
+            EvKeyCode::KEY_F9 => KeyCode::KeyF9,
+            EvKeyCode::KEY_F10 => KeyCode::KeyF10,
+            EvKeyCode::KEY_F11 => KeyCode::KeyF11,
+            EvKeyCode::KEY_F12 => KeyCode::KeyF12,
+            EvKeyCode::KEY_UP => KeyCode::KeyUp,
+            EvKeyCode::KEY_DOWN => KeyCode::KeyDown,
+            EvKeyCode::KEY_LEFT => KeyCode::KeyLeft,
+            EvKeyCode::KEY_RIGHT => KeyCode::KeyRight,
+            EvKeyCode::KEY_RIGHTCTRL => KeyCode::KeyRightCtrl,
+            EvKeyCode::KEY_RIGHTALT => KeyCode::KeyRightAlt,
+            EvKeyCode::KEY_LEFTMETA => KeyCode::KeyLeftMeta,
+            EvKeyCode::KEY_RIGHTMETA => KeyCode::KeyRightMeta,
+            _ => return None,
+        })
+    }
+}
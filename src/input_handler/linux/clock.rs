@@ -0,0 +1,64 @@
+//! Kernel event timestamps and the clock they are taken from.
+
+use std::io;
+use std::os::fd::AsRawFd;
+use std::time::{Duration, Instant, SystemTime};
+use crate::event::{EventClock, EventTime};
+
+/// `EVIOCSCLOCKID`, `_IOW('E', 0xa0, int)`.
+const EVIOCSCLOCKID: u32 = 0x4004_45a0;
+
+/// Makes the kernel timestamp a device's events with the given clock.
+///
+/// The kernel drops events that were queued before the switch.
+pub(super) fn set_clock(device: &evdev::Device, clock: EventClock) -> io::Result<()> {
+    let clock_id = clock_id(clock);
+    // SAFETY: `EVIOCSCLOCKID` reads a single `int` through the pointer, which points to
+    // `clock_id` for the duration of the call, and the descriptor is owned by `device`.
+    let result = unsafe { libc::ioctl(device.as_raw_fd(), EVIOCSCLOCKID as _, &clock_id) };
+    if result < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// Returns the time of an event read while the device used the given clock.
+pub(super) fn event_time(event: &evdev::InputEvent, clock: EventClock) -> EventTime {
+    let timestamp = event.timestamp().duration_since(SystemTime::UNIX_EPOCH).unwrap_or_default();
+    let age = now(clock).saturating_sub(timestamp);
+    let instant = Instant::now();
+    EventTime {
+        clock,
+        timestamp,
+        instant: instant.checked_sub(age).unwrap_or(instant),
+    }
+}
+
+/// Returns the time of an event generated at `instant` rather than read from a device.
+pub(super) fn time_at(instant: Instant, clock: EventClock) -> EventTime {
+    let age = Instant::now().saturating_duration_since(instant);
+    EventTime {
+        clock,
+        timestamp: now(clock).saturating_sub(age),
+        instant,
+    }
+}
+
+fn now(clock: EventClock) -> Duration {
+    let mut time = libc::timespec { tv_sec: 0, tv_nsec: 0 };
+    // SAFETY: `time` is a valid, writable `timespec` for the duration of the call.
+    let result = unsafe { libc::clock_gettime(clock_id(clock), &mut time) };
+    // Both clocks are always available on Linux, but an unreadable clock shouldn't yield
+    // garbage timestamps.
+    if result < 0 {
+        return Duration::ZERO;
+    }
+    Duration::new(time.tv_sec as u64, time.tv_nsec as u32)
+}
+
+fn clock_id(clock: EventClock) -> libc::clockid_t {
+    match clock {
+        EventClock::Realtime => libc::CLOCK_REALTIME,
+        EventClock::Monotonic => libc::CLOCK_MONOTONIC,
+    }
+}
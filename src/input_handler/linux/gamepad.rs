@@ -0,0 +1,406 @@
+//! Gamepad and joystick tracking for evdev devices.
+
+use std::collections::HashMap;
+use evdev::{AbsoluteAxisCode, KeyCode as EvKeyCode};
+use crate::gamepad::mapping::{AxisRange, MappingSource, MappingTarget};
+use crate::gamepad::{
+    AxisCalibration, AxisInfo, Calibration, Gamepad, GamepadAxis, GamepadButton, GamepadId,
+    GamepadIdentity, GamepadMapping, GamepadSettings, MappingDb, Trigger,
+};
+use super::KEY_COUNT;
+
+const ABS_COUNT: usize = 0x40;
+/// Axes from `ABS_MISC` upwards are not treated as joystick axes by SDL.
+const ABS_MISC: u16 = 0x28;
+/// `ABS_HAT0X` through `ABS_HAT3Y` are reported as hats rather than axes.
+const HAT_CODES: std::ops::RangeInclusive<u16> = 0x10..=0x17;
+const TRIGGERS: [Trigger; 2] = [Trigger::Left, Trigger::Right];
+
+/// Raw state of a single gamepad, updated by the input thread.
+pub(super) struct GamepadState {
+    pub(super) device_index: usize,
+    identity: GamepadIdentity,
+    buttons: Vec<bool>,
+    axes: [i32; ABS_COUNT],
+    axis_info: [Option<AxisInfo>; ABS_COUNT],
+    pub(super) settings: GamepadSettings,
+    pub(super) calibration: Calibration,
+    recording: Option<HashMap<u16, AxisCalibration>>,
+    /// Button codes in the order SDL numbers them in mappings
+    button_codes: Vec<u16>,
+    /// Axis codes in the order SDL numbers them in mappings
+    axis_codes: Vec<u16>,
+    mapping: Option<GamepadMapping>,
+    /// Whether the device has gamepad buttons and a right stick on the RX and RY axes, so its
+    /// Z axes are free to be triggers
+    has_trigger_axes: bool,
+    trigger_pressed: [bool; 2],
+}
+
+impl GamepadState {
+    /// Builds the state for a device if it looks like a gamepad or joystick.
+    pub(super) fn detect(device_index: usize, device: &evdev::Device, mappings: &MappingDb) -> Option<Self> {
+        let keys = device.supported_keys()?;
+        if !keys.contains(EvKeyCode::BTN_SOUTH) && !keys.contains(EvKeyCode::BTN_TRIGGER) {
+            return None;
+        }
+
+        // SDL numbers buttons from BTN_JOYSTICK up to KEY_MAX, followed by BTN_MISC up to
+        // BTN_JOYSTICK.
+        let joystick = EvKeyCode::BTN_TRIGGER.code();
+        let button_codes = (joystick..KEY_COUNT as u16)
+            .chain(EvKeyCode::BTN_0.code()..joystick)
+            .filter(|&code| keys.contains(EvKeyCode(code)))
+            .collect();
+        let supported_axes = device.supported_absolute_axes();
+        let has_axis = |axis: AbsoluteAxisCode| supported_axes.is_some_and(|axes| axes.contains(axis));
+        let has_trigger_axes = keys.contains(EvKeyCode::BTN_SOUTH)
+            && has_axis(AbsoluteAxisCode::ABS_RX)
+            && has_axis(AbsoluteAxisCode::ABS_RY);
+        let axis_codes = supported_axes
+            .map(|axes| axes.iter()
+                .map(|axis| axis.0)
+                .filter(|code| *code < ABS_MISC && !HAT_CODES.contains(code))
+                .collect())
+            .unwrap_or_default();
+
+        let mut buttons = vec![false; KEY_COUNT];
+        if let Ok(key_state) = device.get_key_state() {
+            for key in key_state.iter() {
+                if let Some(pressed) = buttons.get_mut(key.code() as usize) {
+                    *pressed = true;
+                }
+            }
+        }
+
+        let mut axes = [0; ABS_COUNT];
+        let mut axis_info = [None; ABS_COUNT];
+        if let Ok(absinfo) = device.get_absinfo() {
+            for (axis, info) in absinfo {
+                let index = axis.0 as usize;
+                axes[index] = info.value();
+                axis_info[index] = Some(AxisInfo {
+                    minimum: info.minimum(),
+                    maximum: info.maximum(),
+                    flat: info.flat(),
+                });
+            }
+        }
+
+        let input_id = device.input_id();
+        let identity = GamepadIdentity {
+            bus_type: input_id.bus_type().0,
+            vendor_id: input_id.vendor(),
+            product_id: input_id.product(),
+            version: input_id.version(),
+            name: device.name().unwrap_or("Unknown gamepad").to_string(),
+        };
+        Some(GamepadState {
+            device_index,
+            mapping: mappings.find(&identity).cloned(),
+            identity,
+            buttons,
+            axes,
+            axis_info,
+            settings: GamepadSettings::new(),
+            calibration: Calibration::new(),
+            recording: None,
+            button_codes,
+            axis_codes,
+            has_trigger_axes,
+            trigger_pressed: [false; 2],
+        })
+    }
+
+    /// Looks the device up again after the mapping database changed.
+    pub(super) fn update_mapping(&mut self, mappings: &MappingDb) {
+        self.mapping = mappings.find(&self.identity).cloned();
+    }
+
+    pub(super) fn set_button(&mut self, code: u16, pressed: bool) {
+        if let Some(state) = self.buttons.get_mut(code as usize) {
+            *state = pressed;
+        }
+    }
+
+    pub(super) fn set_axis(&mut self, code: u16, value: i32) {
+        if let Some(state) = self.axes.get_mut(code as usize) {
+            *state = value;
+        }
+        if let Some(axis) = self.recording.as_mut().and_then(|recording| recording.get_mut(&code)) {
+            axis.record(value);
+        }
+    }
+
+    /// Updates the digital state of the triggers from their analog values.
+    ///
+    /// This runs after every batch of events rather than when a snapshot is taken, so the
+    /// hysteresis sees every intermediate value.
+    pub(super) fn update_triggers(&mut self) {
+        let (_, axes) = self.current_state();
+        for trigger in TRIGGERS {
+            if let Some(value) = self.trigger_value(&axes, trigger) {
+                let pressed = &mut self.trigger_pressed[trigger as usize];
+                *pressed = self.settings.trigger(trigger).update(*pressed, value);
+            }
+        }
+    }
+
+    pub(super) fn identity(&self) -> &GamepadIdentity {
+        &self.identity
+    }
+
+    /// Starts recording axis extremes, taking the current position of each axis as its center.
+    pub(super) fn start_calibration(&mut self) {
+        let recording = self.axis_info.iter().enumerate()
+            .filter(|(_, info)| info.is_some())
+            .map(|(code, _)| (code as u16, AxisCalibration::starting_at(self.axes[code])))
+            .collect();
+        self.recording = Some(recording);
+    }
+
+    /// Stops recording and applies the recorded ranges of the axes that actually moved.
+    pub(super) fn finish_calibration(&mut self) -> Option<Calibration> {
+        let recording = self.recording.take()?;
+        let mut calibration = Calibration::new();
+        for (code, axis) in recording {
+            if axis.minimum < axis.center || axis.maximum > axis.center {
+                calibration.set_axis(code, axis);
+            }
+        }
+        self.calibration = calibration.clone();
+        Some(calibration)
+    }
+
+    /// Takes a snapshot of the current state in the platform-independent form.
+    ///
+    /// Devices with a mapping report the standard layout described by it, others report
+    /// their buttons and axes by evdev code.
+    pub(super) fn snapshot(&self, id: GamepadId) -> Gamepad {
+        let (mut buttons, axes) = self.current_state();
+        let mut triggers = [None; 2];
+        for trigger in TRIGGERS {
+            triggers[trigger as usize] = self.trigger_value(&axes, trigger);
+            if self.trigger_pressed[trigger as usize] && !buttons.contains(&trigger.button()) {
+                buttons.push(trigger.button());
+            }
+        }
+
+        Gamepad {
+            id,
+            identity: self.identity.clone(),
+            mapping_name: self.mapping.as_ref().map(|mapping| mapping.name().to_string()),
+            buttons,
+            axes,
+            triggers,
+        }
+    }
+
+    /// Buttons and processed axes, before triggers are turned into buttons.
+    fn current_state(&self) -> (Vec<GamepadButton>, Vec<(GamepadAxis, f32)>) {
+        let (buttons, mut axes) = match &self.mapping {
+            Some(mapping) => self.mapped_state(mapping),
+            None => self.unmapped_state(),
+        };
+        let triggers = if self.has_triggers() { TRIGGERS.map(Trigger::axis).to_vec() } else { Vec::new() };
+        self.settings.apply(&mut axes, &triggers);
+        (buttons, axes)
+    }
+
+    /// Reads a trigger in `0.0..=1.0` from its axis, which rests at `-1.0`.
+    ///
+    /// Unmapped devices only have triggers when their Z axes aren't needed for a right stick,
+    /// as joysticks and many HID gamepads use them for centered axes.
+    fn trigger_value(&self, axes: &[(GamepadAxis, f32)], trigger: Trigger) -> Option<f32> {
+        if !self.has_triggers() {
+            return None;
+        }
+        let (_, value) = axes.iter().find(|(axis, _)| *axis == trigger.axis())?;
+        Some((value + 1.0) / 2.0)
+    }
+
+    fn has_triggers(&self) -> bool {
+        self.mapping.is_some() || self.has_trigger_axes
+    }
+
+    fn normalized_axis(&self, code: u16) -> Option<f32> {
+        let info = self.axis_info.get(code as usize)?.as_ref()?;
+        let calibration = self.calibration.axis(code).map(|axis| {
+            // A trigger rests at one end of its range, so its recorded center is the released
+            // position rather than the middle of its travel.
+            if self.is_trigger_axis(code) {
+                AxisCalibration { center: axis.minimum + (axis.maximum - axis.minimum) / 2, ..*axis }
+            } else {
+                *axis
+            }
+        });
+        Some(info.normalize(self.axes[code as usize], calibration.as_ref()))
+    }
+
+    /// Checks if a raw axis is a trigger that covers its whole range, from released at one end
+    /// to fully pulled at the other.
+    fn is_trigger_axis(&self, code: u16) -> bool {
+        match &self.mapping {
+            Some(mapping) => mapping.bindings.iter().any(|&(target, source)| match (target, source) {
+                (MappingTarget::Trigger(_), MappingSource::Axis { index, range: AxisRange::Full, .. }) => {
+                    self.axis_codes.get(index) == Some(&code)
+                }
+                _ => false,
+            }),
+            None => {
+                self.has_trigger_axes
+                    && (code == AbsoluteAxisCode::ABS_Z.0 || code == AbsoluteAxisCode::ABS_RZ.0)
+            }
+        }
+    }
+
+    fn unmapped_state(&self) -> (Vec<GamepadButton>, Vec<(GamepadAxis, f32)>) {
+        let mut buttons: Vec<_> = self.buttons.iter().enumerate()
+            .filter(|&(code, &pressed)| pressed && is_button_code(code as u16))
+            .map(|(code, _)| button_from_code(code as u16))
+            .collect();
+
+        // The first hat doubles as the d-pad, unless the device also has d-pad buttons.
+        let dpad_codes = EvKeyCode::BTN_DPAD_UP.code()..=EvKeyCode::BTN_DPAD_RIGHT.code();
+        let has_dpad_buttons = self.button_codes.iter().any(|code| dpad_codes.contains(code));
+        if !has_dpad_buttons && let Some(directions) = self.hat_directions(0) {
+            buttons.extend(HAT_DIRECTIONS.iter()
+                .filter(|(mask, _)| directions & mask != 0)
+                .map(|(_, button)| *button));
+        }
+
+        let axes = (0..ABS_COUNT as u16)
+            .filter_map(|code| Some((axis_from_code(code), self.normalized_axis(code)?)))
+            .collect();
+
+        (buttons, axes)
+    }
+
+    fn mapped_state(&self, mapping: &GamepadMapping) -> (Vec<GamepadButton>, Vec<(GamepadAxis, f32)>) {
+        let mut buttons = Vec::new();
+        let mut axes: Vec<(GamepadAxis, f32)> = Vec::new();
+        let mut set_axis = |axis: GamepadAxis, value: f32, accumulate: bool| {
+            match axes.iter_mut().find(|(existing, _)| *existing == axis) {
+                Some((_, existing)) if accumulate => *existing = (*existing + value).clamp(-1.0, 1.0),
+                Some((_, existing)) => *existing = value,
+                None => axes.push((axis, value)),
+            }
+        };
+
+        for &(target, source) in &mapping.bindings {
+            let Some((value, full_range)) = self.source_value(source) else {
+                continue;
+            };
+            // Buttons, hats and half axes read as `0.0..=1.0`, which covers the whole range
+            // of a full axis.
+            let as_full = if full_range { value } else { value * 2.0 - 1.0 };
+            let as_half = value.max(0.0);
+            match target {
+                MappingTarget::Button(button) => {
+                    if value > 0.5 {
+                        buttons.push(button);
+                    }
+                }
+                MappingTarget::Axis(axis, AxisRange::Full) => set_axis(axis, as_full, false),
+                MappingTarget::Axis(axis, AxisRange::Positive) => set_axis(axis, as_half, true),
+                MappingTarget::Axis(axis, AxisRange::Negative) => set_axis(axis, -as_half, true),
+                MappingTarget::Trigger(trigger) => set_axis(trigger.axis(), as_full, false),
+            }
+        }
+
+        buttons.dedup();
+        (buttons, axes)
+    }
+
+    /// Reads a raw element as a value, and whether it covers the full `-1.0..=1.0` range.
+    fn source_value(&self, source: MappingSource) -> Option<(f32, bool)> {
+        match source {
+            MappingSource::Button(index) => {
+                let code = *self.button_codes.get(index)?;
+                Some((if self.buttons[code as usize] { 1.0 } else { 0.0 }, false))
+            }
+            MappingSource::Axis { index, range, inverted } => {
+                let value = self.normalized_axis(*self.axis_codes.get(index)?)?;
+                let value = if inverted { -value } else { value };
+                match range {
+                    AxisRange::Full => Some((value, true)),
+                    AxisRange::Positive => Some((value.max(0.0), false)),
+                    AxisRange::Negative => Some(((-value).max(0.0), false)),
+                }
+            }
+            MappingSource::Hat { index, mask } => {
+                let directions = self.hat_directions(index)?;
+                Some((if directions & mask == mask { 1.0 } else { 0.0 }, false))
+            }
+        }
+    }
+
+    /// Reads a hat as an SDL direction mask: 1 up, 2 right, 4 down, 8 left.
+    fn hat_directions(&self, index: usize) -> Option<u8> {
+        let x_code = *HAT_CODES.start() as usize + index * 2;
+        if !HAT_CODES.contains(&(x_code as u16)) || self.axis_info[x_code].is_none() {
+            return None;
+        }
+        let x = self.axes[x_code];
+        let y = self.axes[x_code + 1];
+        Some((y < 0) as u8 | ((x > 0) as u8) << 1 | ((y > 0) as u8) << 2 | ((x < 0) as u8) << 3)
+    }
+}
+
+const HAT_DIRECTIONS: [(u8, GamepadButton); 4] = [
+    (1, GamepadButton::DPadUp),
+    (2, GamepadButton::DPadRight),
+    (4, GamepadButton::DPadDown),
+    (8, GamepadButton::DPadLeft),
+];
+
+/// Joystick, gamepad and d-pad button codes, as opposed to keyboard keys.
+fn is_button_code(code: u16) -> bool {
+    (EvKeyCode::BTN_TRIGGER.code()..=EvKeyCode::BTN_THUMBR.code()).contains(&code)
+        || (EvKeyCode::BTN_DPAD_UP.code()..=EvKeyCode::BTN_DPAD_RIGHT.code()).contains(&code)
+        || (EvKeyCode::BTN_TRIGGER_HAPPY1.code()..=EvKeyCode::BTN_TRIGGER_HAPPY40.code()).contains(&code)
+}
+
+fn button_from_code(code: u16) -> GamepadButton {
+    match EvKeyCode(code) {
+        EvKeyCode::BTN_SOUTH => GamepadButton::South,
+        EvKeyCode::BTN_EAST => GamepadButton::East,
+        EvKeyCode::BTN_NORTH => GamepadButton::North,
+        EvKeyCode::BTN_WEST => GamepadButton::West,
+        EvKeyCode::BTN_C => GamepadButton::C,
+        EvKeyCode::BTN_Z => GamepadButton::Z,
+        EvKeyCode::BTN_TL => GamepadButton::LeftTrigger,
+        EvKeyCode::BTN_TL2 => GamepadButton::LeftTrigger2,
+        EvKeyCode::BTN_TR => GamepadButton::RightTrigger,
+        EvKeyCode::BTN_TR2 => GamepadButton::RightTrigger2,
+        EvKeyCode::BTN_SELECT => GamepadButton::Select,
+        EvKeyCode::BTN_START => GamepadButton::Start,
+        EvKeyCode::BTN_MODE => GamepadButton::Mode,
+        EvKeyCode::BTN_THUMBL => GamepadButton::LeftThumb,
+        EvKeyCode::BTN_THUMBR => GamepadButton::RightThumb,
+        EvKeyCode::BTN_DPAD_UP => GamepadButton::DPadUp,
+        EvKeyCode::BTN_DPAD_DOWN => GamepadButton::DPadDown,
+        EvKeyCode::BTN_DPAD_LEFT => GamepadButton::DPadLeft,
+        EvKeyCode::BTN_DPAD_RIGHT => GamepadButton::DPadRight,
+        _ => GamepadButton::Other(code),
+    }
+}
+
+fn axis_from_code(code: u16) -> GamepadAxis {
+    match AbsoluteAxisCode(code) {
+        AbsoluteAxisCode::ABS_X => GamepadAxis::LeftStickX,
+        AbsoluteAxisCode::ABS_Y => GamepadAxis::LeftStickY,
+        AbsoluteAxisCode::ABS_Z => GamepadAxis::LeftZ,
+        AbsoluteAxisCode::ABS_RX => GamepadAxis::RightStickX,
+        AbsoluteAxisCode::ABS_RY => GamepadAxis::RightStickY,
+        AbsoluteAxisCode::ABS_RZ => GamepadAxis::RightZ,
+        AbsoluteAxisCode::ABS_THROTTLE => GamepadAxis::Throttle,
+        AbsoluteAxisCode::ABS_RUDDER => GamepadAxis::Rudder,
+        AbsoluteAxisCode::ABS_WHEEL => GamepadAxis::Wheel,
+        AbsoluteAxisCode::ABS_GAS => GamepadAxis::Gas,
+        AbsoluteAxisCode::ABS_BRAKE => GamepadAxis::Brake,
+        AbsoluteAxisCode::ABS_HAT0X => GamepadAxis::DPadX,
+        AbsoluteAxisCode::ABS_HAT0Y => GamepadAxis::DPadY,
+        _ => GamepadAxis::Other(code),
+    }
+}
@@ -0,0 +1,146 @@
+//! Drawing tablet tracking for evdev devices.
+
+use evdev::{AbsoluteAxisCode, KeyCode as EvKeyCode};
+use crate::stylus::{Stylus, StylusButton, StylusId, ToolType};
+use crate::touch::TouchAxisRange;
+
+/// `BTN_STYLUS3`, which the evdev crate doesn't name.
+const BTN_STYLUS3: u16 = 0x149;
+
+/// Raw state of a single tablet, updated by the input thread.
+pub(super) struct StylusState {
+    pub(super) device_index: usize,
+    name: String,
+    tool: Option<ToolType>,
+    touching: bool,
+    buttons: Vec<StylusButton>,
+    x: i32,
+    y: i32,
+    pressure: i32,
+    distance: i32,
+    tilt_x: i32,
+    tilt_y: i32,
+    x_range: TouchAxisRange,
+    y_range: TouchAxisRange,
+    pressure_range: Option<TouchAxisRange>,
+    has_distance: bool,
+    tilt_ranges: Option<(TouchAxisRange, TouchAxisRange)>,
+}
+
+impl StylusState {
+    /// Builds the state for a device if it has a pen tool and absolute position axes.
+    pub(super) fn detect(device_index: usize, device: &evdev::Device) -> Option<Self> {
+        let keys = device.supported_keys()?;
+        if !keys.contains(EvKeyCode::BTN_TOOL_PEN) {
+            return None;
+        }
+
+        let absinfo: Vec<_> = device.get_absinfo().ok()?.collect();
+        let axis = |code: AbsoluteAxisCode| {
+            absinfo.iter().find(|(axis, _)| *axis == code).map(|(_, info)| (info.value(), TouchAxisRange {
+                minimum: info.minimum(),
+                maximum: info.maximum(),
+                resolution: info.resolution(),
+            }))
+        };
+        let (x, x_range) = axis(AbsoluteAxisCode::ABS_X)?;
+        let (y, y_range) = axis(AbsoluteAxisCode::ABS_Y)?;
+        let pressure = axis(AbsoluteAxisCode::ABS_PRESSURE);
+        let distance = axis(AbsoluteAxisCode::ABS_DISTANCE);
+        let tilt_x = axis(AbsoluteAxisCode::ABS_TILT_X);
+        let tilt_y = axis(AbsoluteAxisCode::ABS_TILT_Y);
+
+        let mut state = StylusState {
+            device_index,
+            name: device.name().unwrap_or("Unknown tablet").to_string(),
+            tool: None,
+            touching: false,
+            buttons: Vec::new(),
+            x,
+            y,
+            pressure: pressure.map_or(0, |(value, _)| value),
+            distance: distance.map_or(0, |(value, _)| value),
+            tilt_x: tilt_x.map_or(0, |(value, _)| value),
+            tilt_y: tilt_y.map_or(0, |(value, _)| value),
+            x_range,
+            y_range,
+            pressure_range: pressure.map(|(_, range)| range),
+            has_distance: distance.is_some(),
+            tilt_ranges: tilt_x.zip(tilt_y).map(|((_, x), (_, y))| (x, y)),
+        };
+        if let Ok(key_state) = device.get_key_state() {
+            for key in key_state.iter() {
+                state.handle_key(key.code(), true);
+            }
+        }
+        Some(state)
+    }
+
+    pub(super) fn handle_key(&mut self, code: u16, pressed: bool) {
+        if let Some(tool) = tool_from_code(code) {
+            if pressed {
+                self.tool = Some(tool);
+            } else if self.tool == Some(tool) {
+                self.tool = None;
+            }
+            return;
+        }
+
+        let button = match EvKeyCode(code) {
+            EvKeyCode::BTN_TOUCH => {
+                self.touching = pressed;
+                return;
+            }
+            EvKeyCode::BTN_STYLUS => StylusButton::Primary,
+            EvKeyCode::BTN_STYLUS2 => StylusButton::Secondary,
+            _ if code == BTN_STYLUS3 => StylusButton::Tertiary,
+            _ => return,
+        };
+        self.buttons.retain(|existing| *existing != button);
+        if pressed {
+            self.buttons.push(button);
+        }
+    }
+
+    pub(super) fn handle_axis(&mut self, axis: AbsoluteAxisCode, value: i32) {
+        match axis {
+            AbsoluteAxisCode::ABS_X => self.x = value,
+            AbsoluteAxisCode::ABS_Y => self.y = value,
+            AbsoluteAxisCode::ABS_PRESSURE => self.pressure = value,
+            AbsoluteAxisCode::ABS_DISTANCE => self.distance = value,
+            AbsoluteAxisCode::ABS_TILT_X => self.tilt_x = value,
+            AbsoluteAxisCode::ABS_TILT_Y => self.tilt_y = value,
+            _ => {}
+        }
+    }
+
+    pub(super) fn snapshot(&self, id: StylusId) -> Stylus {
+        Stylus {
+            id,
+            name: self.name.clone(),
+            tool: self.tool,
+            touching: self.touching,
+            buttons: self.buttons.clone(),
+            x: self.x,
+            y: self.y,
+            x_range: self.x_range,
+            y_range: self.y_range,
+            pressure: self.pressure_range.map(|range| (self.pressure, range)),
+            distance: self.has_distance.then_some(self.distance),
+            tilt: self.tilt_ranges.map(|(x_range, y_range)| ((self.tilt_x, x_range), (self.tilt_y, y_range))),
+        }
+    }
+}
+
+fn tool_from_code(code: u16) -> Option<ToolType> {
+    Some(match EvKeyCode(code) {
+        EvKeyCode::BTN_TOOL_PEN => ToolType::Pen,
+        EvKeyCode::BTN_TOOL_RUBBER => ToolType::Eraser,
+        EvKeyCode::BTN_TOOL_BRUSH => ToolType::Brush,
+        EvKeyCode::BTN_TOOL_PENCIL => ToolType::Pencil,
+        EvKeyCode::BTN_TOOL_AIRBRUSH => ToolType::Airbrush,
+        EvKeyCode::BTN_TOOL_MOUSE => ToolType::Mouse,
+        EvKeyCode::BTN_TOOL_LENS => ToolType::Lens,
+        _ => return None,
+    })
+}
@@ -0,0 +1,169 @@
+//! Multitouch contact tracking for evdev devices, following the type B (slot) protocol.
+
+use std::io;
+use std::os::fd::AsRawFd;
+use std::time::Instant;
+use evdev::{AbsoluteAxisCode, PropType};
+use crate::gesture::{Gesture, GestureConfig, GestureRecognizer};
+use crate::touch::{ScreenMapping, TouchAxisRange, TouchContact, TouchDevice, TouchDeviceId, TouchDeviceKind};
+
+/// `EVIOCGMTSLOTS(len)`, `_IOC(_IOC_READ, 'E', 0x0a, len)`.
+fn eviocgmtslots(len: usize) -> u32 {
+    (2 << 30) | ((len as u32) << 16) | (0x45 << 8) | 0x0a
+}
+
+/// Reads the current value of a multitouch axis in every slot.
+fn read_slots(device: &evdev::Device, axis: AbsoluteAxisCode, slot_count: usize) -> io::Result<Vec<i32>> {
+    // Laid out as the kernel's `input_mt_request_layout`: the axis code, then one value per slot.
+    let mut request = vec![0i32; slot_count + 1];
+    request[0] = axis.0 as i32;
+    let len = std::mem::size_of_val(request.as_slice());
+    // SAFETY: `request` is a writable buffer of exactly the length encoded in the request
+    // number, so the kernel writes within it, and it outlives the call.
+    let result = unsafe { libc::ioctl(device.as_raw_fd(), eviocgmtslots(len) as _, request.as_mut_ptr()) };
+    if result < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    request.remove(0);
+    Ok(request)
+}
+
+/// Contacts of a single touch device, updated by the input thread.
+pub(super) struct TouchState {
+    pub(super) device_index: usize,
+    name: String,
+    kind: TouchDeviceKind,
+    x_range: TouchAxisRange,
+    y_range: TouchAxisRange,
+    /// Slots as they are being updated within the current frame. Like the kernel's, they keep
+    /// their last axis values between contacts, and an id of `-1` marks an empty slot.
+    slots: Vec<TouchContact>,
+    current_slot: usize,
+    /// Contacts as of the last complete frame
+    contacts: Vec<TouchContact>,
+    /// Gestures are only recognized on touchpads; on a touchscreen the contacts are the input
+    gestures: Option<GestureRecognizer>,
+    pub(super) mapping: Option<ScreenMapping>,
+}
+
+impl TouchState {
+    /// Builds the state for a device if it reports multitouch slots.
+    pub(super) fn detect(device_index: usize, device: &evdev::Device, gesture_config: GestureConfig) -> Option<Self> {
+        let axes = device.supported_absolute_axes()?;
+        if !axes.contains(AbsoluteAxisCode::ABS_MT_SLOT) || !axes.contains(AbsoluteAxisCode::ABS_MT_POSITION_X) {
+            return None;
+        }
+
+        let absinfo: Vec<_> = device.get_absinfo().ok()?.collect();
+        let range = |code: AbsoluteAxisCode| {
+            absinfo.iter().find(|(axis, _)| *axis == code).map(|(_, info)| TouchAxisRange {
+                minimum: info.minimum(),
+                maximum: info.maximum(),
+                resolution: info.resolution(),
+            })
+        };
+        let slot_count = range(AbsoluteAxisCode::ABS_MT_SLOT)?.maximum.max(0) as usize + 1;
+
+        let kind = if device.properties().contains(PropType::DIRECT) {
+            TouchDeviceKind::Touchscreen
+        } else {
+            TouchDeviceKind::Touchpad
+        };
+
+        let x_range = range(AbsoluteAxisCode::ABS_MT_POSITION_X)?;
+        let y_range = range(AbsoluteAxisCode::ABS_MT_POSITION_Y)?;
+        let current_slot = absinfo.iter()
+            .find(|(axis, _)| *axis == AbsoluteAxisCode::ABS_MT_SLOT)
+            .map_or(0, |(_, info)| info.value().max(0) as usize);
+
+        // Contacts already on the surface only send events once they change, so the slots
+        // start from the kernel's current state.
+        let read = |axis: AbsoluteAxisCode| {
+            axes.contains(axis).then(|| read_slots(device, axis, slot_count).ok()).flatten()
+        };
+        let ids = read(AbsoluteAxisCode::ABS_MT_TRACKING_ID);
+        let xs = read(AbsoluteAxisCode::ABS_MT_POSITION_X);
+        let ys = read(AbsoluteAxisCode::ABS_MT_POSITION_Y);
+        let pressures = read(AbsoluteAxisCode::ABS_MT_PRESSURE);
+        let majors = read(AbsoluteAxisCode::ABS_MT_TOUCH_MAJOR);
+        let value = |values: &Option<Vec<i32>>, slot: usize| values.as_ref().and_then(|values| values.get(slot).copied());
+        let slots: Vec<_> = (0..slot_count)
+            .map(|slot| TouchContact {
+                id: value(&ids, slot).unwrap_or(-1),
+                x: value(&xs, slot).unwrap_or(0),
+                y: value(&ys, slot).unwrap_or(0),
+                pressure: value(&pressures, slot),
+                touch_major: value(&majors, slot),
+            })
+            .collect();
+        let contacts = slots.iter().filter(|contact| contact.id >= 0).copied().collect();
+
+        Some(TouchState {
+            device_index,
+            name: device.name().unwrap_or("Unknown touch device").to_string(),
+            kind,
+            x_range,
+            y_range,
+            slots,
+            current_slot,
+            contacts,
+            gestures: (kind == TouchDeviceKind::Touchpad)
+                .then(|| GestureRecognizer::new(gesture_config, x_range, y_range)),
+            mapping: None,
+        })
+    }
+
+    /// Applies a single multitouch axis event to the slot it targets.
+    pub(super) fn handle_axis(&mut self, axis: AbsoluteAxisCode, value: i32) {
+        if axis == AbsoluteAxisCode::ABS_MT_SLOT {
+            self.current_slot = value.max(0) as usize;
+            return;
+        }
+        let Some(slot) = self.slots.get_mut(self.current_slot) else {
+            return;
+        };
+
+        // A new contact keeps the slot's other values, since the kernel only sends the ones
+        // that differ from the previous contact's.
+        match axis {
+            AbsoluteAxisCode::ABS_MT_TRACKING_ID => slot.id = value.max(-1),
+            AbsoluteAxisCode::ABS_MT_POSITION_X => slot.x = value,
+            AbsoluteAxisCode::ABS_MT_POSITION_Y => slot.y = value,
+            AbsoluteAxisCode::ABS_MT_PRESSURE => slot.pressure = Some(value),
+            AbsoluteAxisCode::ABS_MT_TOUCH_MAJOR => slot.touch_major = Some(value),
+            _ => {}
+        }
+    }
+
+    /// Publishes the slots once the device reports a complete frame, and returns the gestures
+    /// the new contacts produced, if the device is a touchpad.
+    pub(super) fn end_frame(&mut self, now: Instant) -> Vec<Gesture> {
+        let contacts: Vec<_> = self.slots.iter().filter(|contact| contact.id >= 0).copied().collect();
+        if contacts == self.contacts {
+            return Vec::new();
+        }
+        self.contacts = contacts;
+        match &mut self.gestures {
+            Some(gestures) => gestures.update(&self.contacts, now),
+            None => Vec::new(),
+        }
+    }
+
+    pub(super) fn set_gesture_config(&mut self, config: GestureConfig) {
+        if let Some(gestures) = &mut self.gestures {
+            gestures.set_config(config);
+        }
+    }
+
+    pub(super) fn snapshot(&self, id: TouchDeviceId) -> TouchDevice {
+        TouchDevice {
+            id,
+            name: self.name.clone(),
+            kind: self.kind,
+            x_range: self.x_range,
+            y_range: self.y_range,
+            contacts: self.contacts.clone(),
+            mapping: self.mapping,
+        }
+    }
+}
@@ -15,6 +15,12 @@ use core_graphics::event_source::CGEventSource;
 /// this permission in System Preferences → Security & Privacy → Privacy → Input Monitoring.
 pub struct InputHandler;
 
+impl Default for InputHandler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl InputHandler {
     /// Creates a new input handler.
     pub fn new() -> Self {
@@ -117,6 +123,10 @@ impl InputHandler {
             KeyCode::KeyDown => 0x7D,
             KeyCode::KeyLeft => 0x7B,
             KeyCode::KeyRight => 0x7C,
+            KeyCode::KeyRightCtrl => 0x3E,
+            KeyCode::KeyRightAlt => 0x3D,
+            KeyCode::KeyLeftMeta => 0x37,
+            KeyCode::KeyRightMeta => 0x36,
         }
     }
 }
@@ -9,6 +9,12 @@ use windows::Win32::UI::Input::KeyboardAndMouse::{GetAsyncKeyState, VIRTUAL_KEY}
 /// so it doesn't need a background thread. The state is always current when queried.
 pub struct InputHandler;
 
+impl Default for InputHandler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl InputHandler {
     /// Creates a new input handler.
     pub fn new() -> Self {
@@ -107,6 +113,10 @@ impl InputHandler {
             KeyCode::KeyDown => 0x28,
             KeyCode::KeyLeft => 0x25,
             KeyCode::KeyRight => 0x27,
+            KeyCode::KeyRightCtrl => 0xA3,
+            KeyCode::KeyRightAlt => 0xA5,
+            KeyCode::KeyLeftMeta => 0x5B,
+            KeyCode::KeyRightMeta => 0x5C,
         })
     }
 }
@@ -0,0 +1,333 @@
+//! Multi-stroke keymaps, like Emacs' `Ctrl+X Ctrl+S`, and modal keymap stacks.
+
+use std::collections::HashMap;
+use std::fmt;
+use crate::accelerator::ParseKeyError;
+use crate::event::{InputEvent, KeyState};
+use crate::hotkey::Hotkey;
+use crate::modifiers::Modifiers;
+
+/// Parses space-separated accelerators, such as `Ctrl+X Ctrl+S`, into a list of strokes.
+pub fn parse_strokes(text: &str) -> Result<Vec<Hotkey>, ParseKeyError> {
+    let mut strokes = Vec::new();
+    let mut offset = 0;
+    for part in text.split(' ') {
+        if !part.is_empty() {
+            let stroke = part.parse::<Hotkey>()
+                .map_err(|err| err.offset_by(offset))?;
+            strokes.push(stroke);
+        }
+        offset += part.len() + 1;
+    }
+    if strokes.is_empty() {
+        return Err(ParseKeyError::new("missing key name", "", text.len()));
+    }
+    Ok(strokes)
+}
+
+/// Formats strokes as space-separated accelerators, such as `Ctrl+X Ctrl+S`.
+pub fn format_strokes(strokes: &[Hotkey]) -> String {
+    strokes.iter().map(Hotkey::to_string).collect::<Vec<_>>().join(" ")
+}
+
+/// An error from binding strokes that would make another binding unreachable.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeymapConflict {
+    /// Strokes that were being bound
+    pub strokes: Vec<Hotkey>,
+    /// Strokes of the existing binding they conflict with, or none if no strokes were given
+    pub existing: Vec<Hotkey>,
+}
+
+impl fmt::Display for KeymapConflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (strokes, existing) = (format_strokes(&self.strokes), format_strokes(&self.existing));
+        if self.strokes.is_empty() {
+            write!(f, "no strokes to bind")
+        } else if self.strokes == self.existing {
+            write!(f, "`{}` is already bound", strokes)
+        } else if self.strokes.len() < self.existing.len() {
+            write!(f, "`{}` would shadow `{}`", strokes, existing)
+        } else {
+            write!(f, "`{}` is shadowed by `{}`", strokes, existing)
+        }
+    }
+}
+
+impl std::error::Error for KeymapConflict {}
+
+/// Bindings from sequences of strokes to actions.
+#[derive(Debug, Clone)]
+pub struct Keymap<A> {
+    bindings: Vec<(Vec<Hotkey>, A)>,
+}
+
+impl<A> Default for Keymap<A> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<A> Keymap<A> {
+    /// Creates an empty keymap.
+    pub fn new() -> Self {
+        Keymap { bindings: Vec::new() }
+    }
+
+    /// Binds a sequence of strokes to an action.
+    ///
+    /// Fails if there are no strokes, if the strokes are already bound, or if one binding would
+    /// be a prefix of the other, since the longer one could then never be reached.
+    pub fn bind(&mut self, strokes: impl IntoIterator<Item = Hotkey>, action: A) -> Result<(), KeymapConflict> {
+        let strokes: Vec<_> = strokes.into_iter().collect();
+        if strokes.is_empty() {
+            return Err(KeymapConflict { strokes, existing: Vec::new() });
+        }
+        if let Some((existing, _)) = self.bindings.iter().find(|(existing, _)| {
+            existing.starts_with(&strokes) || strokes.starts_with(existing)
+        }) {
+            return Err(KeymapConflict { strokes, existing: existing.clone() });
+        }
+        self.bindings.push((strokes, action));
+        Ok(())
+    }
+
+    /// Removes the binding for exactly these strokes and returns its action.
+    pub fn unbind(&mut self, strokes: &[Hotkey]) -> Option<A> {
+        let index = self.bindings.iter().position(|(existing, _)| existing == strokes)?;
+        Some(self.bindings.remove(index).1)
+    }
+
+    /// Returns the action bound to exactly these strokes.
+    pub fn get(&self, strokes: &[Hotkey]) -> Option<&A> {
+        self.bindings.iter().find(|(existing, _)| existing == strokes).map(|(_, action)| action)
+    }
+
+    /// Checks if these strokes start a longer binding.
+    pub fn is_prefix(&self, strokes: &[Hotkey]) -> bool {
+        self.bindings.iter().any(|(existing, _)| existing.len() > strokes.len() && existing.starts_with(strokes))
+    }
+
+    /// Returns every binding, in the order they were added.
+    pub fn bindings(&self) -> impl Iterator<Item = (&[Hotkey], &A)> {
+        self.bindings.iter().map(|(strokes, action)| (strokes.as_slice(), action))
+    }
+}
+
+/// What a key press did to a [`ModalKeymap`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeymapOutcome<A> {
+    /// The strokes completed a binding.
+    Action(A),
+    /// The strokes so far are a prefix of a binding; more are needed.
+    Pending(Vec<Hotkey>),
+    /// The strokes aren't bound in the current mode. The pending prefix is cleared.
+    Unbound(Vec<Hotkey>),
+}
+
+/// A stack of modes, such as vim's normal, insert and visual modes, each with its own keymap.
+///
+/// Key presses are looked up in the keymap of the mode on top of the stack. Presses of
+/// modifier keys on their own are ignored, so they don't interrupt a multi-stroke binding, and
+/// so are key repeats, so holding a key triggers its binding once.
+///
+/// ```no_run
+/// use input_query::{InputHandler, Keymap, KeymapOutcome, ModalKeymap, parse_strokes};
+///
+/// let mut normal = Keymap::new();
+/// normal.bind(parse_strokes("Ctrl+X Ctrl+S").unwrap(), "save").unwrap();
+/// normal.bind(parse_strokes("I").unwrap(), "insert").unwrap();
+/// let mut keymaps = ModalKeymap::new("normal");
+/// keymaps.set_keymap("normal", normal);
+///
+/// let handler = InputHandler::new();
+/// for event in handler.subscribe().iter() {
+///     match keymaps.feed(&event) {
+///         Some(KeymapOutcome::Action("insert")) => keymaps.push_mode("insert"),
+///         Some(KeymapOutcome::Action(action)) => println!("{}", action),
+///         _ => {}
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct ModalKeymap<A> {
+    keymaps: HashMap<String, Keymap<A>>,
+    modes: Vec<String>,
+    pending: Vec<Hotkey>,
+}
+
+impl<A: Clone> ModalKeymap<A> {
+    /// Creates a stack holding only the base mode, which can't be popped.
+    pub fn new(base_mode: &str) -> Self {
+        ModalKeymap {
+            keymaps: HashMap::new(),
+            modes: vec![base_mode.to_string()],
+            pending: Vec::new(),
+        }
+    }
+
+    /// Sets the keymap of a mode.
+    pub fn set_keymap(&mut self, mode: &str, keymap: Keymap<A>) {
+        self.keymaps.insert(mode.to_string(), keymap);
+    }
+
+    /// Returns the keymap of a mode, creating an empty one if it has none.
+    pub fn keymap_mut(&mut self, mode: &str) -> &mut Keymap<A> {
+        self.keymaps.entry(mode.to_string()).or_default()
+    }
+
+    /// Returns the current mode.
+    pub fn mode(&self) -> &str {
+        self.modes.last().expect("the base mode is never popped")
+    }
+
+    /// Returns the stack of modes, from the base mode up.
+    pub fn modes(&self) -> &[String] {
+        &self.modes
+    }
+
+    /// Enters a mode. Any pending prefix is cancelled.
+    pub fn push_mode(&mut self, mode: &str) {
+        self.modes.push(mode.to_string());
+        self.pending.clear();
+    }
+
+    /// Returns to the previous mode and returns the one that was left, or `None` if only the
+    /// base mode is left. Any pending prefix is cancelled.
+    pub fn pop_mode(&mut self) -> Option<String> {
+        if self.modes.len() == 1 {
+            return None;
+        }
+        self.pending.clear();
+        self.modes.pop()
+    }
+
+    /// Returns the strokes entered so far of an incomplete binding, for display.
+    pub fn pending(&self) -> &[Hotkey] {
+        &self.pending
+    }
+
+    /// Cancels a pending prefix.
+    pub fn cancel(&mut self) {
+        self.pending.clear();
+    }
+
+    /// Processes an event and returns what it did, or `None` if it wasn't a key press or was a
+    /// press of a modifier key. Key repeats return `None`.
+    pub fn feed(&mut self, event: &InputEvent) -> Option<KeymapOutcome<A>> {
+        let &InputEvent::Key { key, state: KeyState::Pressed, modifiers, .. } = event else {
+            return None;
+        };
+        if Modifiers::from_key(key) != Modifiers::empty() {
+            return None;
+        }
+        Some(self.press(Hotkey::new(modifiers.keys(), key)))
+    }
+
+    /// Processes a stroke as if it was pressed.
+    pub fn press(&mut self, stroke: Hotkey) -> KeymapOutcome<A> {
+        self.pending.push(stroke);
+        let keymap = self.keymaps.get(self.mode());
+        if let Some(action) = keymap.and_then(|keymap| keymap.get(&self.pending)) {
+            let action = action.clone();
+            self.pending.clear();
+            KeymapOutcome::Action(action)
+        } else if keymap.is_some_and(|keymap| keymap.is_prefix(&self.pending)) {
+            KeymapOutcome::Pending(self.pending.clone())
+        } else {
+            KeymapOutcome::Unbound(std::mem::take(&mut self.pending))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, Instant};
+    use crate::event::{EventClock, EventTime};
+    use crate::input_handler::KeyCode;
+
+    fn strokes(text: &str) -> Vec<Hotkey> {
+        parse_strokes(text).unwrap()
+    }
+
+    fn key(key: KeyCode, state: KeyState, modifiers: Modifiers) -> InputEvent {
+        let time = EventTime { clock: EventClock::Monotonic, timestamp: Duration::ZERO, instant: Instant::now() };
+        InputEvent::Key { key, state, modifiers, time }
+    }
+
+    #[test]
+    fn parse_strokes_reports_positions() {
+        assert_eq!(format_strokes(&strokes("Ctrl+X  Ctrl+S")), "Ctrl+X Ctrl+S");
+        assert_eq!(parse_strokes("Ctrl+X Ctrl+Nope").unwrap_err().position(), 12);
+        assert_eq!(parse_strokes(" ").unwrap_err().to_string(), "missing key name at position 1");
+    }
+
+    #[test]
+    fn bindings_that_are_prefixes_conflict() {
+        let mut keymap = Keymap::new();
+        keymap.bind(strokes("Ctrl+X Ctrl+S"), "save").unwrap();
+        keymap.bind(strokes("Ctrl+X Ctrl+F"), "find").unwrap();
+
+        let shadow = keymap.bind(strokes("Ctrl+X"), "cut").unwrap_err();
+        assert_eq!(shadow.existing, strokes("Ctrl+X Ctrl+S"));
+        assert_eq!(shadow.to_string(), "`Ctrl+X` would shadow `Ctrl+X Ctrl+S`");
+
+        let shadowed = keymap.bind(strokes("Ctrl+X Ctrl+S Ctrl+A"), "save-all").unwrap_err();
+        assert_eq!(shadowed.to_string(), "`Ctrl+X Ctrl+S Ctrl+A` is shadowed by `Ctrl+X Ctrl+S`");
+
+        let duplicate = keymap.bind(strokes("Ctrl+X Ctrl+F"), "find-file").unwrap_err();
+        assert_eq!(duplicate.to_string(), "`Ctrl+X Ctrl+F` is already bound");
+        assert_eq!(keymap.get(&strokes("Ctrl+X Ctrl+F")), Some(&"find"));
+    }
+
+    #[test]
+    fn empty_strokes_are_rejected() {
+        let mut keymap = Keymap::<&str>::new();
+        assert_eq!(keymap.bind([], "nothing").unwrap_err().to_string(), "no strokes to bind");
+        keymap.bind(strokes("Ctrl+S"), "save").unwrap();
+    }
+
+    #[test]
+    fn unbinding_frees_the_prefix() {
+        let mut keymap = Keymap::new();
+        keymap.bind(strokes("Ctrl+X Ctrl+S"), "save").unwrap();
+        assert!(keymap.is_prefix(&strokes("Ctrl+X")));
+        assert_eq!(keymap.unbind(&strokes("Ctrl+X Ctrl+S")), Some("save"));
+        assert!(!keymap.is_prefix(&strokes("Ctrl+X")));
+        keymap.bind(strokes("Ctrl+X"), "cut").unwrap();
+    }
+
+    #[test]
+    fn modal_keymaps_follow_prefixes_and_modes() {
+        let mut keymaps = ModalKeymap::new("normal");
+        keymaps.keymap_mut("normal").bind(strokes("Ctrl+X Ctrl+S"), "save").unwrap();
+        keymaps.keymap_mut("insert").bind(strokes("Esc"), "normal").unwrap();
+
+        let ctrl_x = strokes("Ctrl+X");
+        assert_eq!(keymaps.press(ctrl_x[0]), KeymapOutcome::Pending(ctrl_x.clone()));
+        assert_eq!(keymaps.press(strokes("Ctrl+S")[0]), KeymapOutcome::Action("save"));
+        keymaps.press(ctrl_x[0]);
+        assert_eq!(keymaps.press(strokes("Q")[0]), KeymapOutcome::Unbound(strokes("Ctrl+X Q")));
+
+        keymaps.press(ctrl_x[0]);
+        keymaps.push_mode("insert");
+        assert!(keymaps.pending().is_empty());
+        assert_eq!(keymaps.press(strokes("Esc")[0]), KeymapOutcome::Action("normal"));
+        assert_eq!(keymaps.pop_mode().as_deref(), Some("insert"));
+        assert_eq!(keymaps.pop_mode(), None);
+    }
+
+    #[test]
+    fn feed_ignores_modifiers_and_repeats() {
+        let mut keymaps = ModalKeymap::new("normal");
+        keymaps.keymap_mut("normal").bind(strokes("Ctrl+X Ctrl+S"), "save").unwrap();
+
+        assert_eq!(keymaps.feed(&key(KeyCode::KeyLeftCtrl, KeyState::Pressed, Modifiers::CTRL)), None);
+        assert!(matches!(keymaps.feed(&key(KeyCode::KeyX, KeyState::Pressed, Modifiers::CTRL)), Some(KeymapOutcome::Pending(_))));
+        assert_eq!(keymaps.feed(&key(KeyCode::KeyX, KeyState::Repeat, Modifiers::CTRL)), None);
+        assert_eq!(keymaps.feed(&key(KeyCode::KeyX, KeyState::Released, Modifiers::CTRL)), None);
+        let locked = Modifiers::CTRL | Modifiers::CAPS_LOCK;
+        assert_eq!(keymaps.feed(&key(KeyCode::KeyS, KeyState::Pressed, locked)), Some(KeymapOutcome::Action("save")));
+    }
+}
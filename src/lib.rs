@@ -12,6 +12,9 @@
 //! - **Windows**: Uses `GetAsyncKeyState` from the Win32 API. State is queried on-demand.
 //! - **macOS**: Uses `CGEventSourceKeyState` from the Core Graphics framework. State is queried on-demand.
 //!
+//! Everything beyond key queries is only implemented on Linux so far. On Windows and macOS the
+//! same methods exist, but report no devices or events and ignore settings.
+//!
 //! ## Usage
 //!
 //! ```no_run
@@ -35,6 +38,82 @@
 //! }
 //! ```
 //!
+//! ## Gamepads
+//!
+//! On Linux, gamepads and joysticks are read from the same evdev devices as keyboards.
+//! [`InputHandler::gamepads`] returns a snapshot of each connected pad, whose buttons can be
+//! queried by [`GamepadButton`] and whose axes are normalised to `-1.0..=1.0`. Dead zones and
+//! response curves are configured with [`GamepadSettings`], and measured axis ranges can be
+//! recorded and saved per device with [`CalibrationStore`]. Pads with a known SDL
+//! `gamecontrollerdb.txt` mapping report the standard layout regardless of how the driver
+//! numbers their buttons.
+//!
+//! ## Touch
+//!
+//! On Linux, multitouch touchpads and touchscreens are tracked as well.
+//! [`InputHandler::touch_devices`] returns the contacts currently on each surface, with their
+//! position, pressure and contact size. Touchpad gestures (swipes, pinches and taps) are
+//! delivered as [`InputEvent::Gesture`] to receivers returned by [`InputHandler::subscribe`].
+//! Touchscreen positions can be mapped onto display pixels with a [`ScreenMapping`].
+//!
+//! ## Drawing Tablets
+//!
+//! On Linux, pen tablets are read without a display server. [`InputHandler::styluses`] returns
+//! the position, pressure and tilt of each tablet's stylus, along with the tool in proximity
+//! (pen or eraser) and its barrel buttons.
+//!
+//! ## Hotkeys
+//!
+//! On Linux, [`InputHandler::register_hotkey`] runs a callback whenever a [`Hotkey`] is
+//! pressed, in any application and without a display server.
+//!
+//! Hotkeys and key codes can be parsed from and formatted as accelerator strings such as
+//! `Ctrl+Shift+K`, with [`Hotkey::format`] producing `⌃⇧K`-style symbols for macOS menus.
+//!
+//! Key sequences and chords are recognized in the event stream by a [`SequenceMatcher`].
+//!
+//! Multi-stroke bindings such as `Ctrl+X Ctrl+S` and vim-like modes are handled by a
+//! [`ModalKeymap`].
+//!
+//! Dual-role keys, such as Caps Lock acting as Esc when tapped and Ctrl when held, are
+//! resolved by [`TapHold`].
+//!
+//! Double taps and long presses of keys and mouse buttons are recognized by a
+//! [`PressRecognizer`].
+//!
+//! ## Actions
+//!
+//! An [`ActionMap`] binds named actions to keys, mouse buttons and gamepad buttons, and
+//! two-dimensional actions to four keys (such as WASD) or a gamepad stick, so games can query
+//! `"jump"` and `"move"` instead of individual inputs.
+//!
+//! Bindings can be loaded from a file in a subset of TOML with [`ActionMap::load`], and a
+//! [`BindingsWatcher`] reloads the file when it changes, so controls can be tweaked while a game
+//! runs.
+//!
+//! On Linux, [`InputHandler::capture_next`] waits for the next key, button or axis movement and
+//! returns a [`Binding`] for it, for "press a key to bind" prompts.
+//!
+//! A [`ContextStack`] layers action maps, such as a pause menu over gameplay. The top context
+//! consumes inputs, so the contexts below don't see them, unless it is passthrough.
+//!
+//! ## Lock Keys
+//!
+//! On Linux, [`InputHandler::is_toggled`] reports whether Caps Lock, Num Lock or Scroll Lock is
+//! on, as shown by the keyboard LEDs, and [`InputHandler::set_led`] turns those LEDs on and off.
+//!
+//! ## Remapping
+//!
+//! On Linux, a [`RemapTable`] set with [`InputHandler::set_remap_table`] turns physical keys
+//! into logical ones as they are read, such as Caps Lock into Left Ctrl, optionally only on
+//! some devices. Key state, events and hotkeys all see the logical keys.
+//!
+//! ## Switches
+//!
+//! On Linux, hardware switches such as the laptop lid, tablet mode and headphone jack are
+//! tracked as well. [`InputHandler::switch_state`] returns the current state of a
+//! [`SwitchKind`], and changes are delivered as [`InputEvent::Switch`].
+//!
 //! ## Note on Permissions
 //!
 //! - **Linux**: Requires read access to `/dev/input/event*` devices. You may need to add your user
@@ -43,5 +122,46 @@
 //!   "Input Monitoring" permission in System Preferences → Security & Privacy → Privacy.
 //! - **Windows**: No special permissions required.
 
+pub mod accelerator;
+pub mod action;
+pub mod capture;
+pub mod context;
+pub mod event;
+pub mod gamepad;
+pub mod gesture;
+pub mod hotkey;
 pub mod input_handler;
-pub use input_handler::{InputHandler, KeyCode};
+pub mod keymap;
+pub mod modifiers;
+pub mod press;
+pub mod remap;
+pub mod sequence;
+pub mod stylus;
+pub mod switch;
+pub mod tap_hold;
+pub mod touch;
+pub use accelerator::{AcceleratorStyle, ParseKeyError};
+pub use action::{ActionMap, AxisBinding, Binding, BindingsWatcher};
+pub use capture::CaptureFilter;
+pub use context::{ContextStack, InputContext};
+pub use event::{EventClock, EventTime, InputEvent, KeyRepeat, KeyState};
+pub use gamepad::{
+    AxisCalibration, AxisSettings, Calibration, CalibrationStore, Gamepad, GamepadAxis, GamepadButton,
+    GamepadId, GamepadGuid, GamepadIdentity, GamepadMapping, GamepadSettings, MappingDb, ResponseCurve,
+    Stick, StickSettings, Trigger, TriggerSettings,
+};
+pub use gesture::{Gesture, GestureConfig, GestureRecognizer, SwipeDirection};
+pub use hotkey::{Hotkey, HotkeyId};
+pub use input_handler::{InputHandler, KeyCode, LockKey, MouseButton};
+pub use keymap::{Keymap, KeymapConflict, KeymapOutcome, ModalKeymap, format_strokes, parse_strokes};
+pub use modifiers::Modifiers;
+pub use press::{PressConfig, PressEvent, PressGesture, PressInput, PressRecognizer};
+pub use remap::RemapTable;
+pub use sequence::SequenceMatcher;
+pub use stylus::{Stylus, StylusButton, StylusId, ToolType};
+pub use switch::SwitchKind;
+pub use tap_hold::{TapHold, TapHoldConfig};
+pub use touch::{
+    CalibrationMatrix, OutputRect, ParseMatrixError, Rotation, ScreenMapping, TouchAxisRange,
+    TouchContact, TouchDevice, TouchDeviceId, TouchDeviceKind,
+};
@@ -0,0 +1,85 @@
+//! Modifier key state.
+
+use crate::input_handler::KeyCode;
+
+bitflags::bitflags! {
+    /// A set of held modifier keys and active locks.
+    ///
+    /// Flags don't distinguish between the left and right keys: [`CTRL`](Self::CTRL) is set
+    /// while either Ctrl key is held. The right Alt key sets [`ALTGR`](Self::ALTGR) rather than
+    /// [`ALT`](Self::ALT), as it does on most layouts.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+    pub struct Modifiers: u8 {
+        const SHIFT = 1 << 0;
+        const CTRL = 1 << 1;
+        const ALT = 1 << 2;
+        const ALTGR = 1 << 3;
+        /// The Super, Windows or Command key
+        const SUPER = 1 << 4;
+        /// Caps Lock is on
+        const CAPS_LOCK = 1 << 5;
+        /// Num Lock is on
+        const NUM_LOCK = 1 << 6;
+    }
+}
+
+impl Modifiers {
+    /// Flags for held keys, leaving out the lock states.
+    pub const KEYS: Modifiers = Modifiers::SHIFT.union(Modifiers::CTRL).union(Modifiers::ALT)
+        .union(Modifiers::ALTGR).union(Modifiers::SUPER);
+
+    /// Returns the flag a modifier key sets, whichever side it is on, or an empty set for other
+    /// keys.
+    pub fn from_key(key: KeyCode) -> Modifiers {
+        match key {
+            KeyCode::KeyLeftShift | KeyCode::KeyRightShift => Modifiers::SHIFT,
+            KeyCode::KeyLeftCtrl | KeyCode::KeyRightCtrl => Modifiers::CTRL,
+            KeyCode::KeyLeftAlt => Modifiers::ALT,
+            KeyCode::KeyRightAlt => Modifiers::ALTGR,
+            KeyCode::KeyLeftMeta | KeyCode::KeyRightMeta => Modifiers::SUPER,
+            _ => Modifiers::empty(),
+        }
+    }
+
+    /// Returns the flags of the modifier keys `is_pressed` reports as held, leaving out the lock
+    /// states.
+    pub(crate) fn held(is_pressed: impl Fn(KeyCode) -> bool) -> Modifiers {
+        const MODIFIER_KEYS: [KeyCode; 8] = [
+            KeyCode::KeyLeftShift, KeyCode::KeyRightShift, KeyCode::KeyLeftCtrl, KeyCode::KeyRightCtrl,
+            KeyCode::KeyLeftAlt, KeyCode::KeyRightAlt, KeyCode::KeyLeftMeta, KeyCode::KeyRightMeta,
+        ];
+        MODIFIER_KEYS.into_iter()
+            .filter(|key| is_pressed(*key))
+            .fold(Modifiers::empty(), |modifiers, key| modifiers | Modifiers::from_key(key))
+    }
+
+    /// Checks if either Shift key is held.
+    pub fn shift(self) -> bool {
+        self.contains(Modifiers::SHIFT)
+    }
+
+    /// Checks if either Ctrl key is held.
+    pub fn ctrl(self) -> bool {
+        self.contains(Modifiers::CTRL)
+    }
+
+    /// Checks if the left Alt key is held.
+    pub fn alt(self) -> bool {
+        self.contains(Modifiers::ALT)
+    }
+
+    /// Checks if the AltGr (right Alt) key is held.
+    pub fn altgr(self) -> bool {
+        self.contains(Modifiers::ALTGR)
+    }
+
+    /// Checks if either Super key is held.
+    pub fn super_key(self) -> bool {
+        self.contains(Modifiers::SUPER)
+    }
+
+    /// Returns only the held keys, ignoring Caps Lock and Num Lock.
+    pub fn keys(self) -> Modifiers {
+        self & Modifiers::KEYS
+    }
+}
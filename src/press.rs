@@ -0,0 +1,289 @@
+//! Multi-tap and long-press recognition for keys and mouse buttons.
+
+use std::time::{Duration, Instant};
+use crate::event::{InputEvent, KeyState};
+use crate::input_handler::{KeyCode, MouseButton};
+
+/// A key or mouse button whose presses are recognized.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PressInput {
+    Key(KeyCode),
+    Mouse(MouseButton),
+}
+
+/// A recognized way of pressing a key or button.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PressGesture {
+    /// The input was pressed and released `count` times in quick succession.
+    Tap { count: u32 },
+    /// The input has been held for the long-press duration. It is reported while still held.
+    LongPress,
+}
+
+/// A gesture recognized on a key or mouse button.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PressEvent {
+    pub input: PressInput,
+    pub gesture: PressGesture,
+}
+
+/// Timings used to recognize taps and long presses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PressConfig {
+    /// Longest time between releasing the input and pressing it again for the presses to
+    /// count as one multi-tap
+    pub multi_tap_interval: Duration,
+    /// How long the input must be held to count as a long press
+    pub long_press: Duration,
+    /// Taps are reported as soon as this many are counted, without waiting for more
+    pub max_taps: u32,
+}
+
+impl Default for PressConfig {
+    fn default() -> Self {
+        PressConfig {
+            multi_tap_interval: Duration::from_millis(250),
+            long_press: Duration::from_millis(500),
+            max_taps: 3,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct InputState {
+    /// When the input went down, if it is held and no long press was reported yet
+    pressed: Option<Instant>,
+    /// Taps counted so far, and when the last one was released
+    taps: u32,
+    released: Option<Instant>,
+}
+
+/// Recognizes multi-taps and long presses in a stream of key and mouse button events.
+///
+/// A tap is reported once no further tap can follow, that is [`PressConfig::multi_tap_interval`]
+/// after its release, so a double tap is reported as `Tap { count: 2 }` and not also as a
+/// single tap. [`poll`](Self::poll) should be called regularly to report taps and long presses
+/// that complete without another event.
+///
+/// ```no_run
+/// use input_query::{InputHandler, KeyCode, PressConfig, PressGesture, PressInput, PressRecognizer};
+/// use std::time::{Duration, Instant};
+///
+/// let handler = InputHandler::new();
+/// let events = handler.subscribe();
+/// let mut presses = PressRecognizer::new(PressConfig { long_press: Duration::from_secs(2), ..Default::default() });
+/// loop {
+///     let mut recognized = presses.poll(Instant::now());
+///     for event in events.try_iter() {
+///         recognized.extend(presses.feed(&event));
+///     }
+///     for press in recognized {
+///         match (press.input, press.gesture) {
+///             (PressInput::Key(KeyCode::KeyEsc), PressGesture::Tap { count: 2 }) => println!("Cancel"),
+///             (PressInput::Key(KeyCode::KeyEnter), PressGesture::LongPress) => println!("Confirm"),
+///             _ => {}
+///         }
+///     }
+///     std::thread::sleep(Duration::from_millis(10));
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct PressRecognizer {
+    config: PressConfig,
+    /// State of every input seen so far, in the order they were first seen
+    inputs: Vec<(PressInput, InputState)>,
+}
+
+impl PressRecognizer {
+    /// Creates a recognizer with the given timings.
+    pub fn new(config: PressConfig) -> Self {
+        PressRecognizer {
+            config,
+            inputs: Vec::new(),
+        }
+    }
+
+    /// Replaces the timings.
+    pub fn set_config(&mut self, config: PressConfig) {
+        self.config = config;
+    }
+
+    /// Processes an event and returns the gestures it completed. Events other than key and
+    /// mouse button presses and releases are ignored.
+    pub fn feed(&mut self, event: &InputEvent) -> Vec<PressEvent> {
+        let (input, state) = match *event {
+            InputEvent::Key { key, state, .. } => (PressInput::Key(key), state),
+            InputEvent::MouseButton { button, state, .. } => (PressInput::Mouse(button), state),
+            _ => return Vec::new(),
+        };
+        let now = event.time().instant();
+        let mut recognized = self.poll(now);
+        let config = self.config;
+        let index = match self.inputs.iter().position(|(seen, _)| *seen == input) {
+            Some(index) => index,
+            None => {
+                self.inputs.push((input, InputState::default()));
+                self.inputs.len() - 1
+            }
+        };
+        let input_state = &mut self.inputs[index].1;
+
+        match state {
+            KeyState::Pressed => input_state.pressed = Some(now),
+            KeyState::Released => {
+                if input_state.pressed.take().is_some() {
+                    input_state.taps += 1;
+                    input_state.released = Some(now);
+                    if input_state.taps >= config.max_taps {
+                        recognized.push(PressEvent { input, gesture: PressGesture::Tap { count: input_state.taps } });
+                        input_state.taps = 0;
+                    }
+                }
+            }
+            KeyState::Repeat => {}
+        }
+        recognized
+    }
+
+    /// Returns the taps and long presses that completed by `now`, in the order they completed.
+    pub fn poll(&mut self, now: Instant) -> Vec<PressEvent> {
+        let mut recognized = Vec::new();
+        for (input, state) in &mut self.inputs {
+            let taps_ended = state.released
+                .map(|released| released + self.config.multi_tap_interval)
+                .filter(|ended| state.pressed.is_none() && now > *ended);
+            let tap = PressEvent { input: *input, gesture: PressGesture::Tap { count: state.taps } };
+            if state.taps > 0 && let Some(ended) = taps_ended {
+                recognized.push((ended, tap));
+                state.taps = 0;
+            }
+
+            if let Some(pressed) = state.pressed
+                && now.duration_since(pressed) >= self.config.long_press
+            {
+                let held = pressed + self.config.long_press;
+                if state.taps > 0 {
+                    recognized.push((held, tap));
+                    state.taps = 0;
+                }
+                recognized.push((held, PressEvent { input: *input, gesture: PressGesture::LongPress }));
+                state.pressed = None;
+            }
+        }
+        // The sort is stable, so inputs that completed together stay in the order they were
+        // first seen.
+        recognized.sort_by_key(|(completed, _)| *completed);
+        recognized.into_iter().map(|(_, event)| event).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event::{EventClock, EventTime};
+    use crate::modifiers::Modifiers;
+
+    const KEY: PressInput = PressInput::Key(KeyCode::KeyEsc);
+
+    fn key(state: KeyState, start: Instant, millis: u64) -> InputEvent {
+        let time = EventTime { clock: EventClock::Monotonic, timestamp: Duration::ZERO, instant: start }
+            .after(Duration::from_millis(millis));
+        InputEvent::Key { key: KeyCode::KeyEsc, state, modifiers: Modifiers::empty(), time }
+    }
+
+    fn tap(count: u32) -> Vec<PressEvent> {
+        vec![PressEvent { input: KEY, gesture: PressGesture::Tap { count } }]
+    }
+
+    fn long_press() -> Vec<PressEvent> {
+        vec![PressEvent { input: KEY, gesture: PressGesture::LongPress }]
+    }
+
+    #[test]
+    fn taps_wait_out_the_multi_tap_interval() {
+        let start = Instant::now();
+        let mut presses = PressRecognizer::new(PressConfig::default());
+        presses.feed(&key(KeyState::Pressed, start, 0));
+        assert!(presses.feed(&key(KeyState::Released, start, 50)).is_empty());
+        assert!(presses.poll(start + Duration::from_millis(300)).is_empty());
+        assert_eq!(presses.poll(start + Duration::from_millis(301)), tap(1));
+    }
+
+    #[test]
+    fn double_taps_are_reported_once() {
+        let start = Instant::now();
+        let mut presses = PressRecognizer::new(PressConfig::default());
+        presses.feed(&key(KeyState::Pressed, start, 0));
+        presses.feed(&key(KeyState::Released, start, 50));
+        assert!(presses.feed(&key(KeyState::Pressed, start, 300)).is_empty());
+        presses.feed(&key(KeyState::Released, start, 350));
+        assert_eq!(presses.poll(start + Duration::from_millis(601)), tap(2));
+    }
+
+    #[test]
+    fn taps_after_the_interval_start_over() {
+        let start = Instant::now();
+        let mut presses = PressRecognizer::new(PressConfig::default());
+        presses.feed(&key(KeyState::Pressed, start, 0));
+        presses.feed(&key(KeyState::Released, start, 50));
+        assert_eq!(presses.feed(&key(KeyState::Pressed, start, 301)), tap(1));
+    }
+
+    #[test]
+    fn max_taps_are_reported_immediately() {
+        let start = Instant::now();
+        let mut presses = PressRecognizer::new(PressConfig { max_taps: 2, ..Default::default() });
+        presses.feed(&key(KeyState::Pressed, start, 0));
+        presses.feed(&key(KeyState::Released, start, 50));
+        presses.feed(&key(KeyState::Pressed, start, 100));
+        assert_eq!(presses.feed(&key(KeyState::Released, start, 150)), tap(2));
+        assert!(presses.poll(start + Duration::from_secs(1)).is_empty());
+    }
+
+    #[test]
+    fn long_presses_fire_while_held_and_are_not_taps() {
+        let start = Instant::now();
+        let mut presses = PressRecognizer::new(PressConfig::default());
+        presses.feed(&key(KeyState::Pressed, start, 0));
+        assert!(presses.poll(start + Duration::from_millis(499)).is_empty());
+        assert_eq!(presses.poll(start + Duration::from_millis(500)), long_press());
+        assert!(presses.feed(&key(KeyState::Released, start, 600)).is_empty());
+        assert!(presses.poll(start + Duration::from_secs(2)).is_empty());
+    }
+
+    #[test]
+    fn taps_before_a_long_press_are_flushed_first() {
+        let start = Instant::now();
+        let mut presses = PressRecognizer::new(PressConfig::default());
+        presses.feed(&key(KeyState::Pressed, start, 0));
+        presses.feed(&key(KeyState::Released, start, 50));
+        presses.feed(&key(KeyState::Pressed, start, 100));
+        let mut expected = tap(1);
+        expected.extend(long_press());
+        assert_eq!(presses.poll(start + Duration::from_millis(600)), expected);
+    }
+
+    #[test]
+    fn results_are_ordered_by_completion() {
+        let start = Instant::now();
+        let mut presses = PressRecognizer::new(PressConfig::default());
+        let mouse = |state, millis| InputEvent::MouseButton {
+            button: MouseButton::Left,
+            state,
+            time: EventTime { clock: EventClock::Monotonic, timestamp: Duration::ZERO, instant: start }
+                .after(Duration::from_millis(millis)),
+        };
+        // The key is seen first but its tap ends last, after the mouse tap and long press.
+        presses.feed(&key(KeyState::Pressed, start, 0));
+        presses.feed(&mouse(KeyState::Pressed, 10));
+        presses.feed(&mouse(KeyState::Released, 20));
+        presses.feed(&mouse(KeyState::Pressed, 30));
+        presses.feed(&key(KeyState::Released, start, 400));
+        let mouse_input = PressInput::Mouse(MouseButton::Left);
+        assert_eq!(presses.poll(start + Duration::from_secs(1)), [
+            PressEvent { input: mouse_input, gesture: PressGesture::Tap { count: 1 } },
+            PressEvent { input: mouse_input, gesture: PressGesture::LongPress },
+            PressEvent { input: KEY, gesture: PressGesture::Tap { count: 1 } },
+        ]);
+    }
+}
@@ -0,0 +1,161 @@
+//! Key remapping applied before key state is recorded.
+
+use std::collections::HashMap;
+use crate::input_handler::KeyCode;
+
+/// Rules that turn physical keys into the logical keys the rest of the library sees, such as
+/// Caps Lock acting as Left Ctrl.
+///
+/// Rules apply once, so swapping two keys doesn't map them back. Rules scoped to a device,
+/// identified by its name, take precedence over rules for every device, and mapping a key to
+/// itself on a device exempts it from a global rule.
+///
+/// ```no_run
+/// use input_query::{InputHandler, KeyCode, RemapTable};
+///
+/// let mut table = RemapTable::new();
+/// table.remap(KeyCode::KeyCapslock, KeyCode::KeyLeftCtrl);
+/// table.swap_for_device("Apple Inc. Magic Keyboard", KeyCode::KeyLeftAlt, KeyCode::KeyLeftMeta);
+///
+/// let handler = InputHandler::new();
+/// handler.set_remap_table(table);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RemapTable {
+    global: HashMap<KeyCode, KeyCode>,
+    devices: HashMap<String, HashMap<KeyCode, KeyCode>>,
+}
+
+impl RemapTable {
+    /// Creates a table without any rules.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Makes `from` act as `to` on every device.
+    pub fn remap(&mut self, from: KeyCode, to: KeyCode) {
+        self.global.insert(from, to);
+    }
+
+    /// Makes `from` act as `to` on the devices with the given name.
+    pub fn remap_for_device(&mut self, device: &str, from: KeyCode, to: KeyCode) {
+        self.devices.entry(device.to_string()).or_default().insert(from, to);
+    }
+
+    /// Swaps two keys on every device.
+    pub fn swap(&mut self, a: KeyCode, b: KeyCode) {
+        self.remap(a, b);
+        self.remap(b, a);
+    }
+
+    /// Swaps two keys on the devices with the given name.
+    pub fn swap_for_device(&mut self, device: &str, a: KeyCode, b: KeyCode) {
+        self.remap_for_device(device, a, b);
+        self.remap_for_device(device, b, a);
+    }
+
+    /// Removes the rule for `from` on every device, leaving device rules alone.
+    pub fn remove(&mut self, from: KeyCode) {
+        self.global.remove(&from);
+    }
+
+    /// Removes the rule for `from` on the devices with the given name.
+    pub fn remove_for_device(&mut self, device: &str, from: KeyCode) {
+        if let Some(rules) = self.devices.get_mut(device) {
+            rules.remove(&from);
+            if rules.is_empty() {
+                self.devices.remove(device);
+            }
+        }
+    }
+
+    /// Removes every rule.
+    pub fn clear(&mut self) {
+        self.global.clear();
+        self.devices.clear();
+    }
+
+    /// Checks if the table has no rules.
+    pub fn is_empty(&self) -> bool {
+        self.global.is_empty() && self.devices.is_empty()
+    }
+
+    /// Returns the logical key a physical key acts as on the device with the given name.
+    pub fn map(&self, device: &str, key: KeyCode) -> KeyCode {
+        self.devices.get(device)
+            .and_then(|rules| rules.get(&key))
+            .or_else(|| self.global.get(&key))
+            .copied()
+            .unwrap_or(key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEYBOARD: &str = "Apple Inc. Magic Keyboard";
+
+    #[test]
+    fn device_rules_take_precedence() {
+        let mut table = RemapTable::new();
+        table.remap(KeyCode::KeyCapslock, KeyCode::KeyLeftCtrl);
+        table.remap_for_device(KEYBOARD, KeyCode::KeyCapslock, KeyCode::KeyEsc);
+        assert_eq!(table.map(KEYBOARD, KeyCode::KeyCapslock), KeyCode::KeyEsc);
+        assert_eq!(table.map("Other keyboard", KeyCode::KeyCapslock), KeyCode::KeyLeftCtrl);
+        assert_eq!(table.map(KEYBOARD, KeyCode::KeyA), KeyCode::KeyA);
+
+        table.remove_for_device(KEYBOARD, KeyCode::KeyCapslock);
+        assert_eq!(table.map(KEYBOARD, KeyCode::KeyCapslock), KeyCode::KeyLeftCtrl);
+        table.remove(KeyCode::KeyCapslock);
+        assert!(table.is_empty());
+    }
+
+    #[test]
+    fn mapping_a_key_to_itself_exempts_a_device() {
+        let mut table = RemapTable::new();
+        table.remap(KeyCode::KeyCapslock, KeyCode::KeyLeftCtrl);
+        table.remap_for_device(KEYBOARD, KeyCode::KeyCapslock, KeyCode::KeyCapslock);
+        assert_eq!(table.map(KEYBOARD, KeyCode::KeyCapslock), KeyCode::KeyCapslock);
+        assert_eq!(table.map("Other keyboard", KeyCode::KeyCapslock), KeyCode::KeyLeftCtrl);
+
+        table.remap(KeyCode::KeyA, KeyCode::KeyA);
+        assert_eq!(table.map("Other keyboard", KeyCode::KeyA), KeyCode::KeyA);
+    }
+
+    #[test]
+    fn swaps_are_symmetric() {
+        let mut table = RemapTable::new();
+        table.swap(KeyCode::KeyLeftAlt, KeyCode::KeyLeftMeta);
+        assert_eq!(table.map(KEYBOARD, KeyCode::KeyLeftAlt), KeyCode::KeyLeftMeta);
+        assert_eq!(table.map(KEYBOARD, KeyCode::KeyLeftMeta), KeyCode::KeyLeftAlt);
+
+        table.swap_for_device(KEYBOARD, KeyCode::KeyY, KeyCode::KeyZ);
+        assert_eq!(table.map(KEYBOARD, KeyCode::KeyY), KeyCode::KeyZ);
+        assert_eq!(table.map(KEYBOARD, KeyCode::KeyZ), KeyCode::KeyY);
+        assert_eq!(table.map("Other keyboard", KeyCode::KeyZ), KeyCode::KeyZ);
+    }
+
+    #[test]
+    fn chained_rules_apply_once() {
+        let mut table = RemapTable::new();
+        table.remap(KeyCode::KeyA, KeyCode::KeyB);
+        table.remap(KeyCode::KeyB, KeyCode::KeyC);
+        assert_eq!(table.map(KEYBOARD, KeyCode::KeyA), KeyCode::KeyB);
+        assert_eq!(table.map(KEYBOARD, KeyCode::KeyB), KeyCode::KeyC);
+
+        // A device rule isn't followed by a global one either.
+        table.remap_for_device(KEYBOARD, KeyCode::KeyC, KeyCode::KeyA);
+        assert_eq!(table.map(KEYBOARD, KeyCode::KeyC), KeyCode::KeyA);
+    }
+
+    #[test]
+    fn clear_removes_every_rule() {
+        let mut table = RemapTable::new();
+        table.remap(KeyCode::KeyA, KeyCode::KeyB);
+        table.remap_for_device(KEYBOARD, KeyCode::KeyB, KeyCode::KeyA);
+        assert!(!table.is_empty());
+        table.clear();
+        assert_eq!(table, RemapTable::new());
+    }
+}
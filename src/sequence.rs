@@ -0,0 +1,377 @@
+//! Key sequence and chord detection on top of the event stream.
+
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+use crate::event::{InputEvent, KeyState};
+use crate::input_handler::KeyCode;
+
+#[derive(Debug, Clone)]
+enum Pattern {
+    /// Keys pressed one after another, each within `step_timeout` of the previous one
+    Sequence { keys: Vec<KeyCode>, step_timeout: Duration },
+    /// Keys held together, all pressed within `window`
+    Chord { keys: Vec<KeyCode>, window: Duration },
+}
+
+impl Pattern {
+    fn len(&self) -> usize {
+        match self {
+            Pattern::Sequence { keys, .. } | Pattern::Chord { keys, .. } => keys.len(),
+        }
+    }
+}
+
+/// Recognizes registered key sequences and chords in a stream of key events.
+///
+/// Sequences are ordered, like the Konami code: each key must be pressed within a timeout of
+/// the previous one, with no other key in between. Chords are unordered: their keys must all
+/// be held, having gone down within a window of each other.
+///
+/// When registered patterns overlap, the longest match wins. A pattern that completes while a
+/// longer one could still complete is held back until the longer one completes, fails, or
+/// times out, so [`poll`](Self::poll) should be called regularly to flush it.
+///
+/// ```no_run
+/// use input_query::{InputHandler, KeyCode, SequenceMatcher};
+/// use std::time::Duration;
+///
+/// let handler = InputHandler::new();
+/// let events = handler.subscribe();
+/// let mut matcher = SequenceMatcher::new();
+/// matcher.add_sequence("konami", [KeyCode::KeyUp, KeyCode::KeyUp, KeyCode::KeyDown, KeyCode::KeyDown], Duration::from_millis(500));
+/// matcher.add_chord("save-all", [KeyCode::KeyS, KeyCode::KeyA], Duration::from_millis(50));
+/// for event in events.iter() {
+///     for name in matcher.feed(&event) {
+///         println!("{} entered", name);
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct SequenceMatcher<T> {
+    patterns: Vec<(T, Pattern)>,
+    /// Recent presses, oldest first
+    history: VecDeque<(KeyCode, Instant)>,
+    held: HashMap<KeyCode, Instant>,
+    /// A completed pattern held back for a longer one, and when to give up waiting
+    pending: Option<(usize, Instant)>,
+}
+
+impl<T: Clone> Default for SequenceMatcher<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Clone> SequenceMatcher<T> {
+    /// Creates a matcher without any patterns.
+    pub fn new() -> Self {
+        SequenceMatcher {
+            patterns: Vec::new(),
+            history: VecDeque::new(),
+            held: HashMap::new(),
+            pending: None,
+        }
+    }
+
+    /// Registers an ordered sequence. Each key must be pressed within `step_timeout` of the
+    /// previous one. An empty sequence is ignored, since it would match every press.
+    pub fn add_sequence(&mut self, id: T, keys: impl IntoIterator<Item = KeyCode>, step_timeout: Duration) {
+        let keys: Vec<_> = keys.into_iter().collect();
+        if keys.is_empty() {
+            return;
+        }
+        self.patterns.push((id, Pattern::Sequence { keys, step_timeout }));
+    }
+
+    /// Registers an unordered chord. All keys must be held, and must have been pressed within
+    /// `window` of each other. An empty chord is ignored.
+    pub fn add_chord(&mut self, id: T, keys: impl IntoIterator<Item = KeyCode>, window: Duration) {
+        let keys: Vec<_> = keys.into_iter().collect();
+        if keys.is_empty() {
+            return;
+        }
+        self.patterns.push((id, Pattern::Chord { keys, window }));
+    }
+
+    /// Removes every pattern registered with the given id.
+    pub fn remove(&mut self, id: &T) where T: PartialEq {
+        self.patterns.retain(|(registered, _)| registered != id);
+        self.pending = None;
+    }
+
+    /// Forgets all keys seen so far.
+    pub fn reset(&mut self) {
+        self.history.clear();
+        self.held.clear();
+        self.pending = None;
+    }
+
+    /// Processes an event and returns the ids of the patterns it completed. Events other than
+    /// key presses and releases are ignored.
+    pub fn feed(&mut self, event: &InputEvent) -> Vec<T> {
+        let &InputEvent::Key { key, state, time, .. } = event else {
+            return Vec::new();
+        };
+        let now = time.instant();
+        let mut fired = self.poll(now);
+        match state {
+            KeyState::Released => {
+                self.held.remove(&key);
+                return fired;
+            }
+            KeyState::Repeat => return fired,
+            KeyState::Pressed => {}
+        }
+
+        self.held.insert(key, now);
+        self.history.push_back((key, now));
+        let longest = self.patterns.iter().map(|(_, pattern)| pattern.len()).max().unwrap_or(0);
+        while self.history.len() > longest {
+            self.history.pop_front();
+        }
+
+        let completed = (0..self.patterns.len())
+            .filter(|&index| self.completes(&self.patterns[index].1, key))
+            .max_by_key(|&index| self.patterns[index].1.len());
+
+        if let Some((pending, deadline)) = self.pending {
+            let pending_len = self.patterns[pending].1.len();
+            if completed.is_some_and(|index| self.patterns[index].1.len() > pending_len) {
+                self.pending = None;
+            } else if let Some(waiting) = self.waiting_for_longer(pending_len, key) {
+                self.pending = Some((pending, deadline.max(waiting)));
+                return fired;
+            } else {
+                self.pending = None;
+                fired.push(self.fire(pending));
+            }
+        }
+
+        if let Some(index) = completed {
+            match self.waiting_for_longer(self.patterns[index].1.len(), key) {
+                Some(deadline) => self.pending = Some((index, deadline)),
+                None => fired.push(self.fire(index)),
+            }
+        }
+        fired
+    }
+
+    /// Returns the id of a held-back pattern once no longer pattern can complete in time.
+    pub fn poll(&mut self, now: Instant) -> Vec<T> {
+        match self.pending {
+            Some((index, deadline)) if now > deadline => {
+                self.pending = None;
+                vec![self.fire(index)]
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    fn fire(&mut self, index: usize) -> T {
+        self.history.clear();
+        self.patterns[index].0.clone()
+    }
+
+    /// Checks if the latest press of `key` completed a pattern.
+    fn completes(&self, pattern: &Pattern, key: KeyCode) -> bool {
+        match pattern {
+            Pattern::Sequence { keys, step_timeout } => self.sequence_progress(keys, *step_timeout) == keys.len(),
+            Pattern::Chord { keys, window } => {
+                keys.contains(&key) && self.chord_presses(keys, *window).len() == keys.len()
+            }
+        }
+    }
+
+    /// Returns the latest time by which a pattern longer than `len`, and partly entered, could
+    /// still complete.
+    fn waiting_for_longer(&self, len: usize, key: KeyCode) -> Option<Instant> {
+        let (_, last_press) = *self.history.back()?;
+        self.patterns.iter()
+            .filter(|(_, pattern)| pattern.len() > len)
+            .filter_map(|(_, pattern)| match pattern {
+                Pattern::Sequence { keys, step_timeout } => {
+                    let progress = self.sequence_progress(keys, *step_timeout);
+                    (progress > 0 && progress < keys.len()).then_some(last_press + *step_timeout)
+                }
+                Pattern::Chord { keys, window } => {
+                    let presses = self.chord_presses(keys, *window);
+                    let first = presses.iter().min()?;
+                    (keys.contains(&key) && presses.len() < keys.len()).then_some(*first + *window)
+                }
+            })
+            .max()
+    }
+
+    /// Returns how many leading keys of a sequence the latest presses match, up to the whole
+    /// sequence.
+    fn sequence_progress(&self, keys: &[KeyCode], step_timeout: Duration) -> usize {
+        (1..=keys.len().min(self.history.len())).rev()
+            .find(|&count| {
+                let recent = self.history.range(self.history.len() - count..);
+                let in_order = recent.clone().map(|(key, _)| key).eq(&keys[..count]);
+                let in_time = recent.clone().zip(recent.skip(1))
+                    .all(|((_, earlier), (_, later))| later.duration_since(*earlier) <= step_timeout);
+                in_order && in_time
+            })
+            .unwrap_or(0)
+    }
+
+    /// Returns the press times of a chord's keys that are held and went down within `window`
+    /// of the latest one.
+    fn chord_presses(&self, keys: &[KeyCode], window: Duration) -> Vec<Instant> {
+        let presses: Vec<_> = keys.iter().filter_map(|key| self.held.get(key).copied()).collect();
+        let Some(latest) = presses.iter().max().copied() else {
+            return presses;
+        };
+        presses.into_iter().filter(|press| latest.duration_since(*press) <= window).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event::{EventClock, EventTime};
+    use crate::modifiers::Modifiers;
+
+    const STEP: Duration = Duration::from_millis(500);
+
+    fn key(key: KeyCode, state: KeyState, start: Instant, millis: u64) -> InputEvent {
+        let time = EventTime { clock: EventClock::Monotonic, timestamp: Duration::ZERO, instant: start }
+            .after(Duration::from_millis(millis));
+        InputEvent::Key { key, state, modifiers: Modifiers::empty(), time }
+    }
+
+    /// Presses and releases each key in turn, 100 ms apart, and returns everything that fired.
+    fn tap_all(matcher: &mut SequenceMatcher<&'static str>, keys: &[KeyCode], start: Instant) -> Vec<&'static str> {
+        let mut fired = Vec::new();
+        for (index, &pressed) in keys.iter().enumerate() {
+            let millis = index as u64 * 100;
+            fired.extend(matcher.feed(&key(pressed, KeyState::Pressed, start, millis)));
+            fired.extend(matcher.feed(&key(pressed, KeyState::Released, start, millis + 50)));
+        }
+        fired
+    }
+
+    fn konami() -> SequenceMatcher<&'static str> {
+        let mut matcher = SequenceMatcher::new();
+        let keys = [KeyCode::KeyUp, KeyCode::KeyUp, KeyCode::KeyDown, KeyCode::KeyDown];
+        matcher.add_sequence("konami", keys, STEP);
+        matcher
+    }
+
+    #[test]
+    fn sequences_fire_on_their_last_key_in_order() {
+        let start = Instant::now();
+        let mut matcher = konami();
+        let keys = [KeyCode::KeyUp, KeyCode::KeyUp, KeyCode::KeyDown];
+        assert!(tap_all(&mut matcher, &keys, start).is_empty());
+        assert_eq!(matcher.feed(&key(KeyCode::KeyDown, KeyState::Pressed, start, 300)), ["konami"]);
+        // The history is cleared, so another Down doesn't fire again.
+        assert!(matcher.feed(&key(KeyCode::KeyDown, KeyState::Pressed, start, 400)).is_empty());
+
+        let mut matcher = konami();
+        let keys = [KeyCode::KeyUp, KeyCode::KeyDown, KeyCode::KeyUp, KeyCode::KeyDown];
+        assert!(tap_all(&mut matcher, &keys, start).is_empty());
+    }
+
+    #[test]
+    fn unrelated_keys_in_between_break_a_sequence() {
+        let start = Instant::now();
+        let mut matcher = konami();
+        let keys = [KeyCode::KeyUp, KeyCode::KeyUp, KeyCode::KeyX, KeyCode::KeyDown, KeyCode::KeyDown];
+        assert!(tap_all(&mut matcher, &keys, start).is_empty());
+        // Key repeats and releases don't count as keys in between.
+        let mut matcher = konami();
+        matcher.feed(&key(KeyCode::KeyUp, KeyState::Pressed, start, 0));
+        matcher.feed(&key(KeyCode::KeyUp, KeyState::Repeat, start, 50));
+        matcher.feed(&key(KeyCode::KeyX, KeyState::Released, start, 60));
+        let keys = [KeyCode::KeyUp, KeyCode::KeyDown, KeyCode::KeyDown];
+        assert_eq!(tap_all(&mut matcher, &keys, start + Duration::from_millis(100)), ["konami"]);
+    }
+
+    #[test]
+    fn a_slow_step_restarts_the_sequence() {
+        let start = Instant::now();
+        let mut matcher = konami();
+        assert!(tap_all(&mut matcher, &[KeyCode::KeyUp, KeyCode::KeyUp], start).is_empty());
+        let late = start + Duration::from_millis(100) + STEP + Duration::from_millis(1);
+        assert!(tap_all(&mut matcher, &[KeyCode::KeyDown, KeyCode::KeyDown], late).is_empty());
+
+        let again = late + Duration::from_secs(1);
+        let keys = [KeyCode::KeyUp, KeyCode::KeyUp, KeyCode::KeyDown, KeyCode::KeyDown];
+        assert_eq!(tap_all(&mut matcher, &keys, again), ["konami"]);
+    }
+
+    #[test]
+    fn chords_need_every_key_held_within_the_window() {
+        let start = Instant::now();
+        let mut matcher = SequenceMatcher::new();
+        matcher.add_chord("save-all", [KeyCode::KeyS, KeyCode::KeyA], Duration::from_millis(50));
+
+        assert!(matcher.feed(&key(KeyCode::KeyA, KeyState::Pressed, start, 0)).is_empty());
+        assert_eq!(matcher.feed(&key(KeyCode::KeyS, KeyState::Pressed, start, 40)), ["save-all"]);
+        matcher.feed(&key(KeyCode::KeyA, KeyState::Released, start, 100));
+        matcher.feed(&key(KeyCode::KeyS, KeyState::Released, start, 100));
+
+        // Too far apart.
+        assert!(matcher.feed(&key(KeyCode::KeyS, KeyState::Pressed, start, 200)).is_empty());
+        assert!(matcher.feed(&key(KeyCode::KeyA, KeyState::Pressed, start, 260)).is_empty());
+        matcher.feed(&key(KeyCode::KeyA, KeyState::Released, start, 300));
+        matcher.feed(&key(KeyCode::KeyS, KeyState::Released, start, 300));
+
+        // Released before the other key went down.
+        matcher.feed(&key(KeyCode::KeyS, KeyState::Pressed, start, 400));
+        matcher.feed(&key(KeyCode::KeyS, KeyState::Released, start, 410));
+        assert!(matcher.feed(&key(KeyCode::KeyA, KeyState::Pressed, start, 420)).is_empty());
+    }
+
+    #[test]
+    fn the_longest_match_wins() {
+        let start = Instant::now();
+        let mut matcher = SequenceMatcher::new();
+        matcher.add_sequence("g", [KeyCode::KeyG], STEP);
+        matcher.add_sequence("gg", [KeyCode::KeyG, KeyCode::KeyG], STEP);
+        assert_eq!(tap_all(&mut matcher, &[KeyCode::KeyG, KeyCode::KeyG], start), ["gg"]);
+        assert!(matcher.poll(start + Duration::from_secs(2)).is_empty());
+    }
+
+    #[test]
+    fn a_held_back_match_fires_when_the_longer_one_fails() {
+        let start = Instant::now();
+        let mut matcher = SequenceMatcher::new();
+        matcher.add_sequence("g", [KeyCode::KeyG], STEP);
+        matcher.add_sequence("gg", [KeyCode::KeyG, KeyCode::KeyG], STEP);
+        assert!(tap_all(&mut matcher, &[KeyCode::KeyG], start).is_empty());
+        assert_eq!(matcher.feed(&key(KeyCode::KeyX, KeyState::Pressed, start, 100)), ["g"]);
+    }
+
+    #[test]
+    fn poll_flushes_a_held_back_match_after_its_timeout() {
+        let start = Instant::now();
+        let mut matcher = SequenceMatcher::new();
+        matcher.add_sequence("g", [KeyCode::KeyG], STEP);
+        matcher.add_sequence("gg", [KeyCode::KeyG, KeyCode::KeyG], STEP);
+        assert!(tap_all(&mut matcher, &[KeyCode::KeyG], start).is_empty());
+        assert!(matcher.poll(start + STEP).is_empty());
+        assert_eq!(matcher.poll(start + STEP + Duration::from_millis(1)), ["g"]);
+        assert!(matcher.poll(start + STEP * 2).is_empty());
+
+        // A later key flushes it too, before being handled itself.
+        assert!(tap_all(&mut matcher, &[KeyCode::KeyG], start + STEP * 4).is_empty());
+        let later = start + STEP * 6;
+        assert_eq!(matcher.feed(&key(KeyCode::KeyG, KeyState::Pressed, later, 0)), ["g"]);
+    }
+
+    #[test]
+    fn reset_and_remove_drop_progress() {
+        let start = Instant::now();
+        let mut matcher = konami();
+        assert!(tap_all(&mut matcher, &[KeyCode::KeyUp, KeyCode::KeyUp, KeyCode::KeyDown], start).is_empty());
+        matcher.reset();
+        assert!(matcher.feed(&key(KeyCode::KeyDown, KeyState::Pressed, start, 300)).is_empty());
+
+        matcher.remove(&"konami");
+        let keys = [KeyCode::KeyUp, KeyCode::KeyUp, KeyCode::KeyDown, KeyCode::KeyDown];
+        assert!(tap_all(&mut matcher, &keys, start + Duration::from_secs(1)).is_empty());
+    }
+}
@@ -0,0 +1,135 @@
+//! Platform-independent drawing tablet and stylus types.
+//!
+//! Tablets are currently only read by the Linux backend.
+
+use crate::touch::TouchAxisRange;
+
+/// Identifies a connected drawing tablet.
+///
+/// Ids are assigned when the [`InputHandler`](crate::InputHandler) is created and stay valid
+/// for its lifetime.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct StylusId(pub(crate) usize);
+
+/// The tool held near the tablet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ToolType {
+    Pen,
+    /// The eraser end of a pen
+    Eraser,
+    Brush,
+    Pencil,
+    Airbrush,
+    /// A tablet puck
+    Mouse,
+    Lens,
+}
+
+/// A button on the barrel of a stylus.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StylusButton {
+    /// The button nearest to the tip
+    Primary,
+    Secondary,
+    Tertiary,
+}
+
+/// A snapshot of a drawing tablet's stylus at the time it was queried.
+#[derive(Debug, Clone)]
+pub struct Stylus {
+    pub(crate) id: StylusId,
+    pub(crate) name: String,
+    pub(crate) tool: Option<ToolType>,
+    pub(crate) touching: bool,
+    pub(crate) buttons: Vec<StylusButton>,
+    pub(crate) x: i32,
+    pub(crate) y: i32,
+    pub(crate) x_range: TouchAxisRange,
+    pub(crate) y_range: TouchAxisRange,
+    pub(crate) pressure: Option<(i32, TouchAxisRange)>,
+    pub(crate) distance: Option<i32>,
+    pub(crate) tilt: Option<((i32, TouchAxisRange), (i32, TouchAxisRange))>,
+}
+
+impl Stylus {
+    /// Returns the id of this tablet.
+    pub fn id(&self) -> StylusId {
+        self.id
+    }
+
+    /// Returns the name reported by the device.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the tool in proximity of the tablet, or `None` if no tool is near it.
+    pub fn tool(&self) -> Option<ToolType> {
+        self.tool
+    }
+
+    /// Checks if a tool is close enough for the tablet to track it.
+    pub fn in_proximity(&self) -> bool {
+        self.tool.is_some()
+    }
+
+    /// Checks if the tip of the tool is touching the tablet.
+    pub fn is_touching(&self) -> bool {
+        self.touching
+    }
+
+    /// Checks if a barrel button was pressed when the snapshot was taken.
+    pub fn is_pressed(&self, button: StylusButton) -> bool {
+        self.buttons.contains(&button)
+    }
+
+    /// Returns the position of the tool in device units.
+    pub fn position(&self) -> (i32, i32) {
+        (self.x, self.y)
+    }
+
+    /// Returns the position of the tool normalised to `0.0..=1.0` on each axis.
+    pub fn normalized_position(&self) -> (f32, f32) {
+        (normalize(self.x, self.x_range), normalize(self.y, self.y_range))
+    }
+
+    /// Returns the range of horizontal positions.
+    pub fn x_range(&self) -> TouchAxisRange {
+        self.x_range
+    }
+
+    /// Returns the range of vertical positions.
+    pub fn y_range(&self) -> TouchAxisRange {
+        self.y_range
+    }
+
+    /// Returns the tip pressure normalised to `0.0..=1.0`, or `None` if the tablet doesn't
+    /// report pressure.
+    pub fn pressure(&self) -> Option<f32> {
+        self.pressure.map(|(value, range)| normalize(value, range))
+    }
+
+    /// Returns the raw hover distance, or `None` if the tablet doesn't report it.
+    pub fn distance(&self) -> Option<i32> {
+        self.distance
+    }
+
+    /// Returns the tilt of the tool along the X and Y axes, in degrees from vertical.
+    ///
+    /// Tablets that don't report the resolution of their tilt axes are assumed to report
+    /// degrees directly. Returns `None` if the tablet doesn't report tilt.
+    pub fn tilt(&self) -> Option<(f32, f32)> {
+        let degrees = |(value, range): (i32, TouchAxisRange)| {
+            if range.resolution > 0 {
+                (value as f32 / range.resolution as f32).to_degrees()
+            } else {
+                value as f32
+            }
+        };
+        self.tilt.map(|(x, y)| (degrees(x), degrees(y)))
+    }
+}
+
+fn normalize(value: i32, range: TouchAxisRange) -> f32 {
+    let span = (range.maximum - range.minimum).max(1) as f32;
+    ((value - range.minimum) as f32 / span).clamp(0.0, 1.0)
+}
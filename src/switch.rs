@@ -0,0 +1,40 @@
+//! Platform-independent switch types.
+//!
+//! Switches are currently only read by the Linux backend.
+
+/// A two-state switch reported by the hardware, such as a laptop lid.
+///
+/// A switch is active when the condition its name describes holds: [`Lid`](Self::Lid) is
+/// active while the lid is closed and [`HeadphoneInserted`](Self::HeadphoneInserted) while
+/// headphones are plugged in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SwitchKind {
+    /// The lid is closed
+    Lid,
+    /// The device is in tablet mode, e.g. a convertible folded over
+    TabletMode,
+    HeadphoneInserted,
+    /// All radios are disabled by a hardware kill switch
+    RadiosDisabled,
+    MicrophoneInserted,
+    /// The device is plugged into a docking station
+    Docked,
+    LineOutInserted,
+    /// A plug is physically in the jack, whatever its type
+    JackInserted,
+    VideoOutInserted,
+    CameraLensCovered,
+    /// The keypad slide is out
+    KeypadSlide,
+    /// Something is close to the front of the device
+    FrontProximity,
+    /// Screen rotation is locked
+    RotateLock,
+    LineInInserted,
+    /// The device is muted
+    Muted,
+    /// The pen is in its holder
+    PenInserted,
+    /// The machine's cover is closed
+    MachineCover,
+}
@@ -0,0 +1,341 @@
+//! Dual-role keys that act as one key when tapped and another when held.
+
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
+use crate::event::{EventTime, InputEvent, KeyState};
+use crate::input_handler::KeyCode;
+use crate::modifiers::Modifiers;
+
+/// When a dual-role key counts as held, following QMK's options of the same names.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TapHoldConfig {
+    /// How long the key must be held before it counts as held
+    pub tapping_term: Duration,
+    /// Count as held when another key is pressed and released before the dual-role key is
+    /// released, even within the tapping term
+    pub permissive_hold: bool,
+    /// Count as held as soon as another key is pressed
+    pub hold_on_other_key_press: bool,
+}
+
+impl Default for TapHoldConfig {
+    fn default() -> Self {
+        TapHoldConfig {
+            tapping_term: Duration::from_millis(200),
+            permissive_hold: false,
+            hold_on_other_key_press: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct DualRole {
+    tap: KeyCode,
+    hold: KeyCode,
+}
+
+/// A dual-role key that was pressed and isn't resolved yet.
+#[derive(Debug, Clone)]
+struct Undecided {
+    key: KeyCode,
+    role: DualRole,
+    pressed: EventTime,
+    /// Events that arrived in the meantime, replayed once the key is resolved
+    buffer: Vec<InputEvent>,
+    /// Other keys pressed in the meantime
+    others: Vec<KeyCode>,
+}
+
+/// Turns a stream of physical key events into logical ones, resolving dual-role keys.
+///
+/// A dual-role key, such as Caps Lock acting as Esc when tapped and Ctrl when held, produces
+/// no events until it is resolved. It is a tap if released within the tapping term and a hold
+/// otherwise, unless [`TapHoldConfig`] says another key decides it sooner. Events that arrive
+/// before then are held back and replayed afterwards, in order. Other events pass through,
+/// with their modifiers updated to reflect resolved holds.
+///
+/// [`poll`](Self::poll) should be called regularly, so that keys held past the tapping term
+/// resolve without waiting for the next event.
+///
+/// ```no_run
+/// use input_query::{InputHandler, KeyCode, TapHold, TapHoldConfig};
+///
+/// let mut tap_hold = TapHold::new(TapHoldConfig::default());
+/// tap_hold.add_key(KeyCode::KeyCapslock, KeyCode::KeyEsc, KeyCode::KeyLeftCtrl);
+/// tap_hold.add_key(KeyCode::KeySpace, KeyCode::KeySpace, KeyCode::KeyLeftShift);
+///
+/// let handler = InputHandler::new();
+/// for event in handler.subscribe().iter() {
+///     for event in tap_hold.feed(&event) {
+///         println!("{:?}", event);
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct TapHold {
+    config: TapHoldConfig,
+    roles: HashMap<KeyCode, DualRole>,
+    undecided: Option<Undecided>,
+    /// Dual-role keys resolved as held, and the key they hold
+    holding: HashMap<KeyCode, KeyCode>,
+    /// Logical keys currently down, to compute modifiers
+    down: HashSet<KeyCode>,
+    /// Lock states from the latest input event
+    locks: Modifiers,
+}
+
+impl TapHold {
+    /// Creates a resolver without any dual-role keys.
+    pub fn new(config: TapHoldConfig) -> Self {
+        TapHold {
+            config,
+            roles: HashMap::new(),
+            undecided: None,
+            holding: HashMap::new(),
+            down: HashSet::new(),
+            locks: Modifiers::empty(),
+        }
+    }
+
+    /// Makes `key` act as `tap` when tapped and as `hold` when held.
+    pub fn add_key(&mut self, key: KeyCode, tap: KeyCode, hold: KeyCode) {
+        self.roles.insert(key, DualRole { tap, hold });
+    }
+
+    /// Makes `key` an ordinary key again.
+    pub fn remove_key(&mut self, key: KeyCode) {
+        self.roles.remove(&key);
+    }
+
+    /// Replaces the configuration, for keys pressed from now on.
+    pub fn set_config(&mut self, config: TapHoldConfig) {
+        self.config = config;
+    }
+
+    /// Processes an event and returns the logical events it resolved.
+    pub fn feed(&mut self, event: &InputEvent) -> Vec<InputEvent> {
+        let mut output = self.poll(event.time().instant());
+        if let InputEvent::Key { modifiers, .. } = event {
+            self.locks = *modifiers - Modifiers::KEYS;
+        }
+        self.process(event.clone(), &mut output);
+        output
+    }
+
+    /// Resolves a dual-role key held past the tapping term, and returns the events that
+    /// resolved.
+    pub fn poll(&mut self, now: Instant) -> Vec<InputEvent> {
+        let mut output = Vec::new();
+        if let Some(undecided) = &self.undecided {
+            let deadline = undecided.pressed.after(self.config.tapping_term);
+            if now >= deadline.instant() {
+                self.resolve_hold(deadline, &mut output);
+            }
+        }
+        output
+    }
+
+    fn process(&mut self, event: InputEvent, output: &mut Vec<InputEvent>) {
+        let InputEvent::Key { key, state, time, .. } = event else {
+            match &mut self.undecided {
+                Some(undecided) => undecided.buffer.push(event),
+                None => output.push(event),
+            }
+            return;
+        };
+
+        if let Some(undecided) = &mut self.undecided {
+            if key == undecided.key {
+                if state == KeyState::Released {
+                    self.resolve_tap(time, output);
+                }
+                return;
+            }
+            match state {
+                KeyState::Pressed if self.config.hold_on_other_key_press => {
+                    self.resolve_hold(time, output);
+                    self.process(event, output);
+                }
+                KeyState::Pressed => {
+                    undecided.others.push(key);
+                    undecided.buffer.push(event);
+                }
+                KeyState::Released if self.config.permissive_hold && undecided.others.contains(&key) => {
+                    self.resolve_hold(time, output);
+                    self.process(event, output);
+                }
+                _ => undecided.buffer.push(event),
+            }
+            return;
+        }
+
+        if let Some(&hold) = self.holding.get(&key) {
+            if state == KeyState::Released {
+                self.holding.remove(&key);
+            }
+            self.emit(hold, state, time, output);
+        } else if let Some(&role) = self.roles.get(&key) {
+            if state == KeyState::Pressed {
+                self.undecided = Some(Undecided { key, role, pressed: time, buffer: Vec::new(), others: Vec::new() });
+            }
+        } else {
+            self.emit(key, state, time, output);
+        }
+    }
+
+    fn resolve_tap(&mut self, time: EventTime, output: &mut Vec<InputEvent>) {
+        let Some(undecided) = self.undecided.take() else {
+            return;
+        };
+        self.emit(undecided.role.tap, KeyState::Pressed, time, output);
+        self.emit(undecided.role.tap, KeyState::Released, time, output);
+        for event in undecided.buffer {
+            self.process(event, output);
+        }
+    }
+
+    fn resolve_hold(&mut self, time: EventTime, output: &mut Vec<InputEvent>) {
+        let Some(undecided) = self.undecided.take() else {
+            return;
+        };
+        self.holding.insert(undecided.key, undecided.role.hold);
+        self.emit(undecided.role.hold, KeyState::Pressed, time, output);
+        for event in undecided.buffer {
+            self.process(event, output);
+        }
+    }
+
+    fn emit(&mut self, key: KeyCode, state: KeyState, time: EventTime, output: &mut Vec<InputEvent>) {
+        match state {
+            KeyState::Pressed => {
+                self.down.insert(key);
+            }
+            KeyState::Released => {
+                self.down.remove(&key);
+            }
+            KeyState::Repeat => {}
+        }
+        let modifiers = self.down.iter()
+            .fold(self.locks, |modifiers, key| modifiers | Modifiers::from_key(*key));
+        output.push(InputEvent::Key { key, state, modifiers, time });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event::EventClock;
+
+    fn time(start: Instant, millis: u64) -> EventTime {
+        EventTime { clock: EventClock::Monotonic, timestamp: Duration::ZERO, instant: start }
+            .after(Duration::from_millis(millis))
+    }
+
+    fn key(key: KeyCode, state: KeyState, start: Instant, millis: u64) -> InputEvent {
+        InputEvent::Key { key, state, modifiers: Modifiers::empty(), time: time(start, millis) }
+    }
+
+    /// Keys, states and modifiers of the output, leaving out times.
+    fn keys(events: Vec<InputEvent>) -> Vec<(KeyCode, KeyState, Modifiers)> {
+        events.into_iter()
+            .filter_map(|event| match event {
+                InputEvent::Key { key, state, modifiers, .. } => Some((key, state, modifiers)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn caps_as_esc_and_ctrl(config: TapHoldConfig) -> TapHold {
+        let mut tap_hold = TapHold::new(config);
+        tap_hold.add_key(KeyCode::KeyCapslock, KeyCode::KeyEsc, KeyCode::KeyLeftCtrl);
+        tap_hold
+    }
+
+    #[test]
+    fn release_within_the_tapping_term_taps() {
+        let start = Instant::now();
+        let mut tap_hold = caps_as_esc_and_ctrl(TapHoldConfig::default());
+        assert!(tap_hold.feed(&key(KeyCode::KeyCapslock, KeyState::Pressed, start, 0)).is_empty());
+        assert_eq!(
+            keys(tap_hold.feed(&key(KeyCode::KeyCapslock, KeyState::Released, start, 199))),
+            [(KeyCode::KeyEsc, KeyState::Pressed, Modifiers::empty()), (KeyCode::KeyEsc, KeyState::Released, Modifiers::empty())],
+        );
+    }
+
+    #[test]
+    fn release_at_the_tapping_term_holds() {
+        let start = Instant::now();
+        let mut tap_hold = caps_as_esc_and_ctrl(TapHoldConfig::default());
+        tap_hold.feed(&key(KeyCode::KeyCapslock, KeyState::Pressed, start, 0));
+        assert_eq!(
+            keys(tap_hold.feed(&key(KeyCode::KeyCapslock, KeyState::Released, start, 200))),
+            [(KeyCode::KeyLeftCtrl, KeyState::Pressed, Modifiers::CTRL), (KeyCode::KeyLeftCtrl, KeyState::Released, Modifiers::empty())],
+        );
+    }
+
+    #[test]
+    fn poll_resolves_holds_at_the_deadline() {
+        let start = Instant::now();
+        let mut tap_hold = caps_as_esc_and_ctrl(TapHoldConfig::default());
+        tap_hold.feed(&key(KeyCode::KeyCapslock, KeyState::Pressed, start, 0));
+        assert!(tap_hold.poll(time(start, 199).instant()).is_empty());
+        let resolved = tap_hold.poll(time(start, 250).instant());
+        assert_eq!(resolved[0].time().instant(), time(start, 200).instant());
+        assert_eq!(keys(resolved), [(KeyCode::KeyLeftCtrl, KeyState::Pressed, Modifiers::CTRL)]);
+    }
+
+    #[test]
+    fn other_keys_are_replayed_after_a_tap() {
+        let start = Instant::now();
+        let mut tap_hold = caps_as_esc_and_ctrl(TapHoldConfig::default());
+        tap_hold.feed(&key(KeyCode::KeyCapslock, KeyState::Pressed, start, 0));
+        assert!(tap_hold.feed(&key(KeyCode::KeyA, KeyState::Pressed, start, 50)).is_empty());
+        assert!(tap_hold.feed(&key(KeyCode::KeyA, KeyState::Released, start, 80)).is_empty());
+        assert_eq!(
+            keys(tap_hold.feed(&key(KeyCode::KeyCapslock, KeyState::Released, start, 100))),
+            [
+                (KeyCode::KeyEsc, KeyState::Pressed, Modifiers::empty()),
+                (KeyCode::KeyEsc, KeyState::Released, Modifiers::empty()),
+                (KeyCode::KeyA, KeyState::Pressed, Modifiers::empty()),
+                (KeyCode::KeyA, KeyState::Released, Modifiers::empty()),
+            ],
+        );
+    }
+
+    #[test]
+    fn permissive_hold_holds_on_a_nested_tap() {
+        let start = Instant::now();
+        let mut tap_hold = caps_as_esc_and_ctrl(TapHoldConfig { permissive_hold: true, ..Default::default() });
+        tap_hold.feed(&key(KeyCode::KeyCapslock, KeyState::Pressed, start, 0));
+        assert!(tap_hold.feed(&key(KeyCode::KeyA, KeyState::Pressed, start, 50)).is_empty());
+        assert_eq!(
+            keys(tap_hold.feed(&key(KeyCode::KeyA, KeyState::Released, start, 80))),
+            [
+                (KeyCode::KeyLeftCtrl, KeyState::Pressed, Modifiers::CTRL),
+                (KeyCode::KeyA, KeyState::Pressed, Modifiers::CTRL),
+                (KeyCode::KeyA, KeyState::Released, Modifiers::CTRL),
+            ],
+        );
+    }
+
+    #[test]
+    fn permissive_hold_ignores_keys_pressed_before() {
+        let start = Instant::now();
+        let mut tap_hold = caps_as_esc_and_ctrl(TapHoldConfig { permissive_hold: true, ..Default::default() });
+        tap_hold.feed(&key(KeyCode::KeyA, KeyState::Pressed, start, 0));
+        tap_hold.feed(&key(KeyCode::KeyCapslock, KeyState::Pressed, start, 10));
+        assert!(tap_hold.feed(&key(KeyCode::KeyA, KeyState::Released, start, 50)).is_empty());
+        assert_eq!(keys(tap_hold.feed(&key(KeyCode::KeyCapslock, KeyState::Released, start, 100)))[0].0, KeyCode::KeyEsc);
+    }
+
+    #[test]
+    fn hold_on_other_key_press_holds_immediately() {
+        let start = Instant::now();
+        let mut tap_hold = caps_as_esc_and_ctrl(TapHoldConfig { hold_on_other_key_press: true, ..Default::default() });
+        tap_hold.feed(&key(KeyCode::KeyCapslock, KeyState::Pressed, start, 0));
+        assert_eq!(
+            keys(tap_hold.feed(&key(KeyCode::KeyA, KeyState::Pressed, start, 10))),
+            [(KeyCode::KeyLeftCtrl, KeyState::Pressed, Modifiers::CTRL), (KeyCode::KeyA, KeyState::Pressed, Modifiers::CTRL)],
+        );
+    }
+}
@@ -0,0 +1,311 @@
+//! Platform-independent touch device types.
+//!
+//! Touch devices are currently only read by the Linux backend, which tracks contacts using the
+//! kernel's multitouch (type B) protocol.
+
+use std::fmt;
+use std::str::FromStr;
+
+/// Identifies a connected touch device.
+///
+/// Ids are assigned when the [`InputHandler`](crate::InputHandler) is created and stay valid
+/// for its lifetime.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TouchDeviceId(pub(crate) usize);
+
+/// What kind of surface a touch device is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TouchDeviceKind {
+    /// An indirect surface that moves a pointer, such as a laptop touchpad
+    Touchpad,
+    /// A surface on top of a display, touched directly
+    Touchscreen,
+}
+
+/// The range of a raw touch axis, as reported by the device.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TouchAxisRange {
+    /// Smallest value the axis reports
+    pub minimum: i32,
+    /// Largest value the axis reports
+    pub maximum: i32,
+    /// Units per millimetre, or `0` if the device doesn't report it
+    pub resolution: i32,
+}
+
+/// A finger (or other contact) currently on a touch surface.
+///
+/// Positions are in device units; see [`TouchDevice::x_range`] and [`TouchDevice::y_range`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TouchContact {
+    /// Identifies the contact for as long as it stays on the surface
+    pub id: i32,
+    /// Horizontal position
+    pub x: i32,
+    /// Vertical position
+    pub y: i32,
+    /// Contact pressure, if the device reports it
+    pub pressure: Option<i32>,
+    /// Length of the major axis of the contact area, if the device reports it
+    pub touch_major: Option<i32>,
+}
+
+/// How the display is rotated relative to the touch panel, clockwise.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Rotation {
+    #[default]
+    Deg0,
+    Deg90,
+    Deg180,
+    Deg270,
+}
+
+/// The area of the display a touchscreen covers, in pixels.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OutputRect {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+/// An error from parsing a calibration matrix.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseMatrixError(String);
+
+impl fmt::Display for ParseMatrixError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for ParseMatrixError {}
+
+/// A calibration matrix in the form libinput uses for `LIBINPUT_CALIBRATION_MATRIX`.
+///
+/// The six values `a b c d e f` are the first two rows of a 3x3 affine matrix, applied to
+/// positions normalised to `0.0..=1.0`:
+///
+/// ```text
+/// x' = a * x + b * y + c
+/// y' = d * x + e * y + f
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CalibrationMatrix(pub [f32; 6]);
+
+impl Default for CalibrationMatrix {
+    fn default() -> Self {
+        CalibrationMatrix([1.0, 0.0, 0.0, 0.0, 1.0, 0.0])
+    }
+}
+
+impl CalibrationMatrix {
+    fn apply(&self, (x, y): (f32, f32)) -> (f32, f32) {
+        let [a, b, c, d, e, f] = self.0;
+        (a * x + b * y + c, d * x + e * y + f)
+    }
+}
+
+impl FromStr for CalibrationMatrix {
+    type Err = ParseMatrixError;
+
+    /// Parses six whitespace-separated numbers, as found in udev's
+    /// `LIBINPUT_CALIBRATION_MATRIX` property.
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let values = text.split_whitespace()
+            .map(|value| value.parse::<f32>().map_err(|_| ParseMatrixError(format!("invalid number `{}`", value))))
+            .collect::<Result<Vec<_>, _>>()?;
+        let values: [f32; 6] = values.try_into()
+            .map_err(|values: Vec<f32>| ParseMatrixError(format!("expected 6 values, found {}", values.len())))?;
+        Ok(CalibrationMatrix(values))
+    }
+}
+
+/// Maps touchscreen positions onto display pixels.
+///
+/// Positions are normalised to the device's range, transformed by the calibration matrix,
+/// rotated to match the display and finally scaled onto the output rectangle.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ScreenMapping {
+    /// Area of the display covered by the touchscreen
+    pub output: OutputRect,
+    /// Rotation of the display relative to the panel
+    pub rotation: Rotation,
+    /// Calibration applied before the rotation
+    pub calibration: CalibrationMatrix,
+}
+
+impl ScreenMapping {
+    /// Creates a mapping onto the given rectangle, without rotation or calibration.
+    pub fn new(output: OutputRect) -> Self {
+        ScreenMapping {
+            output,
+            rotation: Rotation::Deg0,
+            calibration: CalibrationMatrix::default(),
+        }
+    }
+
+    /// Sets the rotation of the display.
+    pub fn with_rotation(mut self, rotation: Rotation) -> Self {
+        self.rotation = rotation;
+        self
+    }
+
+    /// Sets the calibration matrix.
+    pub fn with_calibration(mut self, calibration: CalibrationMatrix) -> Self {
+        self.calibration = calibration;
+        self
+    }
+
+    /// Maps a position normalised to `0.0..=1.0` onto display pixels.
+    pub fn map_normalized(&self, x: f32, y: f32) -> (f32, f32) {
+        let (x, y) = self.calibration.apply((x, y));
+        let (x, y) = match self.rotation {
+            Rotation::Deg0 => (x, y),
+            Rotation::Deg90 => (1.0 - y, x),
+            Rotation::Deg180 => (1.0 - x, 1.0 - y),
+            Rotation::Deg270 => (y, 1.0 - x),
+        };
+        (self.output.x + x * self.output.width, self.output.y + y * self.output.height)
+    }
+}
+
+/// A snapshot of a touch device's contacts at the time it was queried.
+#[derive(Debug, Clone)]
+pub struct TouchDevice {
+    pub(crate) id: TouchDeviceId,
+    pub(crate) name: String,
+    pub(crate) kind: TouchDeviceKind,
+    pub(crate) x_range: TouchAxisRange,
+    pub(crate) y_range: TouchAxisRange,
+    pub(crate) contacts: Vec<TouchContact>,
+    pub(crate) mapping: Option<ScreenMapping>,
+}
+
+impl TouchDevice {
+    /// Returns the id of this device.
+    pub fn id(&self) -> TouchDeviceId {
+        self.id
+    }
+
+    /// Returns the name reported by the device.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns whether the device is a touchpad or a touchscreen.
+    pub fn kind(&self) -> TouchDeviceKind {
+        self.kind
+    }
+
+    /// Returns the range of horizontal positions.
+    pub fn x_range(&self) -> TouchAxisRange {
+        self.x_range
+    }
+
+    /// Returns the range of vertical positions.
+    pub fn y_range(&self) -> TouchAxisRange {
+        self.y_range
+    }
+
+    /// Returns the contacts that were on the surface when the snapshot was taken.
+    pub fn contacts(&self) -> &[TouchContact] {
+        &self.contacts
+    }
+
+    /// Returns a contact by id, if it was on the surface when the snapshot was taken.
+    pub fn contact(&self, id: i32) -> Option<&TouchContact> {
+        self.contacts.iter().find(|contact| contact.id == id)
+    }
+
+    /// Returns the screen mapping set for this device, if any.
+    pub fn screen_mapping(&self) -> Option<&ScreenMapping> {
+        self.mapping.as_ref()
+    }
+
+    /// Returns a contact's position normalised to `0.0..=1.0` on each axis.
+    pub fn normalized_position(&self, contact: &TouchContact) -> (f32, f32) {
+        let normalize = |value: i32, range: TouchAxisRange| {
+            let span = (range.maximum - range.minimum).max(1) as f32;
+            (value - range.minimum) as f32 / span
+        };
+        (normalize(contact.x, self.x_range), normalize(contact.y, self.y_range))
+    }
+
+    /// Returns a contact's position in display pixels, or `None` if no screen mapping was set.
+    pub fn screen_position(&self, contact: &TouchContact) -> Option<(f32, f32)> {
+        let (x, y) = self.normalized_position(contact);
+        Some(self.mapping?.map_normalized(x, y))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OUTPUT: OutputRect = OutputRect { x: 100.0, y: 50.0, width: 200.0, height: 100.0 };
+
+    fn assert_close((x, y): (f32, f32), expected: (f32, f32)) {
+        assert!((x - expected.0).abs() < 1e-3 && (y - expected.1).abs() < 1e-3, "{:?} != {:?}", (x, y), expected);
+    }
+
+    #[test]
+    fn maps_onto_the_output_for_each_rotation() {
+        let mapping = ScreenMapping::new(OUTPUT);
+        assert_close(mapping.map_normalized(0.25, 0.1), (150.0, 60.0));
+        assert_close(mapping.with_rotation(Rotation::Deg90).map_normalized(0.25, 0.1), (280.0, 75.0));
+        assert_close(mapping.with_rotation(Rotation::Deg180).map_normalized(0.25, 0.1), (250.0, 140.0));
+        assert_close(mapping.with_rotation(Rotation::Deg270).map_normalized(0.25, 0.1), (120.0, 125.0));
+    }
+
+    #[test]
+    fn rotations_keep_corners_on_corners() {
+        let corners = [(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)];
+        let pixels = [(100.0, 50.0), (300.0, 50.0), (300.0, 150.0), (100.0, 150.0)];
+        let rotations = [Rotation::Deg0, Rotation::Deg90, Rotation::Deg180, Rotation::Deg270];
+        for (turns, rotation) in rotations.into_iter().enumerate() {
+            let mapping = ScreenMapping::new(OUTPUT).with_rotation(rotation);
+            for (index, (x, y)) in corners.into_iter().enumerate() {
+                // Rotating clockwise moves each corner one place further round.
+                assert_close(mapping.map_normalized(x, y), pixels[(index + turns) % 4]);
+            }
+        }
+    }
+
+    #[test]
+    fn flips_are_applied_before_the_rotation() {
+        let flip_x: CalibrationMatrix = "-1 0 1 0 1 0".parse().unwrap();
+        let flip_y: CalibrationMatrix = "1 0 0 0 -1 1".parse().unwrap();
+        let mapping = ScreenMapping::new(OUTPUT);
+        assert_close(mapping.with_calibration(flip_x).map_normalized(0.25, 0.1), (250.0, 60.0));
+        assert_close(mapping.with_calibration(flip_y).map_normalized(0.25, 0.1), (150.0, 140.0));
+        let rotated = mapping.with_calibration(flip_x).with_rotation(Rotation::Deg90);
+        assert_close(rotated.map_normalized(0.25, 0.1), (280.0, 125.0));
+    }
+
+    #[test]
+    fn parses_calibration_matrices() {
+        assert_eq!("1 0 0 0 1 0".parse(), Ok(CalibrationMatrix::default()));
+        assert_eq!(
+            "  0 -1 1\t1 0 0.5\n".parse(),
+            Ok(CalibrationMatrix([0.0, -1.0, 1.0, 1.0, 0.0, 0.5])),
+        );
+        assert_eq!(
+            "1 0 0 0 1".parse::<CalibrationMatrix>().unwrap_err().to_string(),
+            "expected 6 values, found 5",
+        );
+        assert_eq!(
+            "1 0 0 0 1 0 0".parse::<CalibrationMatrix>().unwrap_err().to_string(),
+            "expected 6 values, found 7",
+        );
+        assert_eq!("".parse::<CalibrationMatrix>().unwrap_err().to_string(), "expected 6 values, found 0");
+        assert_eq!(
+            "1 0 x 0 1 0".parse::<CalibrationMatrix>().unwrap_err().to_string(),
+            "invalid number `x`",
+        );
+        assert_eq!(
+            "1,0,0,0,1,0".parse::<CalibrationMatrix>().unwrap_err().to_string(),
+            "invalid number `1,0,0,0,1,0`",
+        );
+    }
+}